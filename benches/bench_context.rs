@@ -0,0 +1,101 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+use seq_pow::{
+    bigint::Integer, context::VerifierContext, limits::VerifyLimits, pie19, util, wes19,
+};
+
+/// Blocks verified per iteration, all under one pubkey and target like a chain from one miner.
+const NUM_BLOCKS: u32 = 8;
+const NUM_STEPS: u64 = 1_000;
+
+fn bench_context(c: &mut Criterion) {
+    // RSA-2048 modulus, taken from [Wikipedia](https://en.wikipedia.org/wiki/RSA_numbers#RSA-2048).
+    const MODULUS: &str =
+      "251959084756578934940271832400483985714292821262040320277771378360436620207075955562640185258807\
+      8440691829064124951508218929855914917618450280848912007284499268739280728777673597141834727026189\
+      6375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172\
+      6546322822168699875491824224336372590851418654620435767984233871847744479207399342365848238242811\
+      9816381501067481045166037730605620161967625613384414360383390441495263443219011465754445417842402\
+      0924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951\
+      378636564391212010397122822120720357";
+    let modulus = Integer::from_str_radix(MODULUS, 10).unwrap();
+
+    let (_, pubkey) = ecvrf::keygen();
+    // every state meets it, so each block is mined in `NUM_STEPS`
+    let target = Integer::from(1) << 256u32;
+    let limits = VerifyLimits::default();
+    let gs: Vec<Integer> = (0..NUM_BLOCKS)
+        .map(|seed| util::h_g(&modulus, &pubkey, &Integer::from(seed)))
+        .collect();
+
+    // the free functions build a context per block, `verify_with_context` shares one
+    let blocks: Vec<_> = gs
+        .iter()
+        .map(|g| {
+            let (y, pi, iters) = wes19::mine(NUM_STEPS, NUM_STEPS, &pubkey, &modulus, g, &target);
+            (g, y, pi, iters)
+        })
+        .collect();
+    let mut group = c.benchmark_group(format!("wes19 {} blocks", NUM_BLOCKS));
+    group.bench_function("verify", |b| {
+        b.iter(|| {
+            for (g, y, pi, iters) in &blocks {
+                let verified = wes19::verify(
+                    &modulus, g, *iters, NUM_STEPS, y, pi, &pubkey, &target, &limits,
+                );
+                assert!(verified.is_ok());
+            }
+        })
+    });
+    group.bench_function("verify_with_context", |b| {
+        b.iter(|| {
+            let ctx = VerifierContext::new(&modulus, &pubkey, &target);
+            for (g, y, pi, iters) in &blocks {
+                let verified =
+                    wes19::verify_with_context(&ctx, g, *iters, NUM_STEPS, y, pi, &limits);
+                assert!(verified.is_ok());
+            }
+        })
+    });
+    group.finish();
+
+    let blocks: Vec<_> = gs
+        .iter()
+        .map(|g| {
+            let (y, proof, iters) =
+                pie19::mine(NUM_STEPS, NUM_STEPS, 4, &pubkey, &modulus, g, &target);
+            (g, y, proof, iters)
+        })
+        .collect();
+    let mut group = c.benchmark_group(format!("pie19 {} blocks", NUM_BLOCKS));
+    group.bench_function("verify", |b| {
+        b.iter(|| {
+            for (g, y, proof, iters) in &blocks {
+                let verified = pie19::verify(
+                    &modulus, g, y, *iters, NUM_STEPS, proof, &pubkey, &target, &limits,
+                );
+                assert!(verified.is_ok());
+            }
+        })
+    });
+    group.bench_function("verify_with_context", |b| {
+        b.iter(|| {
+            let ctx = VerifierContext::new(&modulus, &pubkey, &target);
+            for (g, y, proof, iters) in &blocks {
+                let verified =
+                    pie19::verify_with_context(&ctx, g, y, *iters, NUM_STEPS, proof, &limits);
+                assert!(verified.is_ok());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_context
+}
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion};
+use seq_pow::{bigint::Integer, checkpoint::Checkpoints, squaring::Squarer, util, wes19};
+
+fn bench_parallel(c: &mut Criterion) {
+    // RSA-2048 modulus, taken from [Wikipedia](https://en.wikipedia.org/wiki/RSA_numbers#RSA-2048).
+    const MODULUS: &str =
+      "251959084756578934940271832400483985714292821262040320277771378360436620207075955562640185258807\
+      8440691829064124951508218929855914917618450280848912007284499268739280728777673597141834727026189\
+      6375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172\
+      6546322822168699875491824224336372590851418654620435767984233871847744479207399342365848238242811\
+      9816381501067481045166037730605620161967625613384414360383390441495263443219011465754445417842402\
+      0924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951\
+      378636564391212010397122822120720357";
+    let modulus = Integer::from_str_radix(MODULUS, 10).unwrap();
+
+    // use 256-bit for block header hash
+    const PREV_BLOCK_HASH: &str =
+        "1eeb30c7163271850b6d018e8282093ac6755a771da6267edf6c9b4fce9242ba";
+    let seed = Integer::from_str_radix(PREV_BLOCK_HASH, 16).unwrap();
+
+    let (_, pubkey) = ecvrf::keygen();
+    let g = util::h_g(&modulus, &pubkey, &seed);
+
+    // 64 segments, enough to keep every thread of a typical machine busy
+    let num_steps = 64_000;
+    let mut checkpoints = Checkpoints::new(&modulus, &g, num_steps / 64);
+    let mut y = g.clone();
+    checkpoints.advance(&mut Squarer::new(&modulus), &mut y, num_steps);
+    let y = util::to_qr_plus(&modulus, &y);
+
+    // the segments are independent, so the time should drop about linearly with the threads
+    let mut group = c.benchmark_group("wes19::prove_with_checkpoints() threads");
+    let max_threads = rayon::current_num_threads();
+    let mut threads = 1;
+    while threads <= max_threads {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, _| {
+            b.iter(|| {
+                pool.install(|| {
+                    wes19::prove_with_checkpoints(&modulus, &g, num_steps, &y, &checkpoints)
+                })
+            })
+        });
+        threads *= 2;
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_parallel
+}
+criterion_main!(benches);
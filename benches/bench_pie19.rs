@@ -2,8 +2,7 @@
 extern crate criterion;
 
 use criterion::Criterion;
-use rug::Integer;
-use seq_pow::{pie19, util};
+use seq_pow::{bigint::Integer, pie19, util};
 
 // FOR BENCHMARKING ONLY
 // NO SECURITY CHECK
@@ -26,7 +25,7 @@ pub fn verify(
     let mut t = total_num_steps;
     let two: Integer = 2u64.into();
     for mu_i in pi_list {
-        let r_i = util::hash_fs(modulus, &[&x_i, &y_i, &mu_i]);
+        let r_i = util::hash_fs(modulus, &[&x_i, &y_i, mu_i]);
 
         let xi_ri = x_i.clone().pow_mod(&r_i, modulus).unwrap();
         x_i = (xi_ri * mu_i.clone()).div_rem_floor(modulus.clone()).1;
@@ -34,8 +33,8 @@ pub fn verify(
         let mui_ri = mu_i.clone().pow_mod(&r_i, modulus).unwrap();
         y_i = (mui_ri * y_i.clone()).div_rem_floor(modulus.clone()).1;
 
-        t = t / 2;
-        if (t % 2 != 0) && (t != 1) {
+        t /= 2;
+        if !t.is_multiple_of(2) && (t != 1) {
             t += 1;
             y_i = y_i.clone().pow_mod(&two, modulus).unwrap();
         }
@@ -53,14 +52,14 @@ fn bench_pie19(c: &mut Criterion) {
                        target: &Integer| {
         c.bench_function(
             &format!("pie19::solve() with num_steps {}", num_steps),
-            move |b| b.iter(|| pie19::solve(modulus, state, num_steps, pubkey, target)),
+            move |b| b.iter(|| pie19::solve(modulus, state, num_steps, num_steps, pubkey, target)),
         );
     };
     let bench_prove =
         |c: &mut Criterion, num_steps: u64, modulus: &Integer, g: &Integer, y: &Integer| {
             c.bench_function(
                 &format!("pie19::prove() with num_steps {}", num_steps),
-                move |b| b.iter(|| pie19::prove(modulus, g, y, num_steps)),
+                move |b| b.iter(|| pie19::prove(modulus, g, y, num_steps, 0)),
             );
         };
     let bench_verify = |c: &mut Criterion,
@@ -94,13 +93,13 @@ fn bench_pie19(c: &mut Criterion) {
     const TARGET_HASH: &str = "07fb30c7163271850b6d018e8282093ac6755a771da6267edf6c9b4fce9242ba";
 
     let seed_hash = Integer::from_str_radix(PREV_BLOCK_HASH, 16).unwrap();
-    let seed = Integer::from(seed_hash.div_rem_floor(modulus.clone()).1);
+    let seed = seed_hash.div_rem_floor(modulus.clone()).1;
     println!("seed:\t\t0x{:064x}", seed);
 
     let target_hash = Integer::from_str_radix(TARGET_HASH, 16).unwrap();
-    let target = Integer::from(target_hash.div_rem_floor(modulus.clone()).1);
+    let target = target_hash.div_rem_floor(modulus.clone()).1;
     println!("target:\t\t0x{:064x}", target);
-    println!("");
+    println!();
 
     let (_, pubkey) = ecvrf::keygen();
     let g = util::h_g(&modulus, &pubkey, &seed);
@@ -110,8 +109,8 @@ fn bench_pie19(c: &mut Criterion) {
     ];
 
     for &num_steps in &num_steps_arr {
-        let (y, _) = pie19::solve(&modulus, &g, num_steps, &pubkey, &target);
-        let pi_list = pie19::prove(&modulus, &g, &y, num_steps);
+        let (y, _, _) = pie19::solve(&modulus, &g, num_steps, num_steps, &pubkey, &target);
+        let pi_list = pie19::prove(&modulus, &g, &y, num_steps, 0).pi_list;
 
         bench_solve(c, num_steps, &modulus, &g, &pubkey, &target);
         bench_prove(c, num_steps, &modulus, &g, &y);
@@ -2,8 +2,7 @@
 extern crate criterion;
 
 use criterion::Criterion;
-use rug::Integer;
-use seq_pow::{sloth, util};
+use seq_pow::{bigint::Integer, sloth, util};
 
 // FOR BENCHMARKING ONLY
 // NO SECURITY CHECK
@@ -37,7 +36,7 @@ pub fn verify(
         // {
         //     return false;
         // }
-        util::validate_difficulty(&util::h_state(modulus, pubkey, &cur_state), target)
+        let _ = util::validate_difficulty(&util::h_state(modulus, pubkey, &cur_state), target)
             && util::validate_difficulty(&util::h_state(modulus, pubkey, &cur_state_inv), target);
     }
 
@@ -54,7 +53,7 @@ fn bench_sloth(c: &mut Criterion) {
                        target: &Integer| {
         c.bench_function(
             &format!("sloth::solve() with num_steps {}", num_steps),
-            move |b| b.iter(|| sloth::solve(modulus, state, num_steps, pubkey, target)),
+            move |b| b.iter(|| sloth::solve(modulus, state, num_steps, num_steps, pubkey, target)),
         );
     };
     let bench_verify = |c: &mut Criterion,
@@ -80,13 +79,13 @@ fn bench_sloth(c: &mut Criterion) {
     const TARGET_HASH: &str = "07fb30c7163271850b6d018e8282093ac6755a771da6267edf6c9b4fce9242ba";
 
     let seed_hash = Integer::from_str_radix(PREV_BLOCK_HASH, 16).unwrap();
-    let seed = Integer::from(seed_hash.div_rem_floor(modulus.clone()).1);
+    let seed = seed_hash.div_rem_floor(modulus.clone()).1;
     println!("seed:\t\t0x{:064x}", seed);
 
     let target_hash = Integer::from_str_radix(TARGET_HASH, 16).unwrap();
-    let target = Integer::from(target_hash.div_rem_floor(modulus.clone()).1);
+    let target = target_hash.div_rem_floor(modulus.clone()).1;
     println!("target:\t\t0x{:064x}", target);
-    println!("");
+    println!();
 
     let (_, pubkey) = ecvrf::keygen();
     let g = util::h_g(&modulus, &pubkey, &seed);
@@ -0,0 +1,64 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion, Throughput};
+use seq_pow::{bigint::Integer, squaring::Squarer};
+
+// the loop wes19::solve and pie19::solve used before the squaring engine
+fn naive_square_n(modulus: &Integer, state: &Integer, num_steps: u64) -> Integer {
+    let mut y = state.clone();
+    for _ in 0..num_steps {
+        y = y.clone() * y.clone();
+        y = y.div_rem_floor(modulus.clone()).1;
+    }
+    y
+}
+
+fn engine_square_n(modulus: &Integer, state: &Integer, num_steps: u64) -> Integer {
+    let mut squarer = Squarer::new(modulus);
+    let mut y = state.clone();
+    squarer.square_n(&mut y, num_steps);
+    y
+}
+
+fn bench_squaring(c: &mut Criterion) {
+    // RSA-2048 modulus, taken from [Wikipedia](https://en.wikipedia.org/wiki/RSA_numbers#RSA-2048).
+    const MODULUS: &str =
+      "251959084756578934940271832400483985714292821262040320277771378360436620207075955562640185258807\
+      8440691829064124951508218929855914917618450280848912007284499268739280728777673597141834727026189\
+      6375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172\
+      6546322822168699875491824224336372590851418654620435767984233871847744479207399342365848238242811\
+      9816381501067481045166037730605620161967625613384414360383390441495263443219011465754445417842402\
+      0924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951\
+      378636564391212010397122822120720357";
+    let modulus = Integer::from_str_radix(MODULUS, 10).unwrap();
+
+    // use 256-bit for block header hash
+    const PREV_BLOCK_HASH: &str =
+        "1eeb30c7163271850b6d018e8282093ac6755a771da6267edf6c9b4fce9242ba";
+    let state = Integer::from_str_radix(PREV_BLOCK_HASH, 16).unwrap();
+
+    let mut group = c.benchmark_group("squaring");
+    for &num_steps in &[1_000u64, 10_000, 100_000] {
+        // report squarings per second
+        group.throughput(Throughput::Elements(num_steps));
+        group.bench_with_input(
+            BenchmarkId::new("naive loop", num_steps),
+            &num_steps,
+            |b, &n| b.iter(|| naive_square_n(&modulus, &state, n)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Squarer", num_steps),
+            &num_steps,
+            |b, &n| b.iter(|| engine_square_n(&modulus, &state, n)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_squaring
+}
+criterion_main!(benches);
@@ -2,8 +2,7 @@
 extern crate criterion;
 
 use criterion::Criterion;
-use rug::Integer;
-use seq_pow::{util, wes19};
+use seq_pow::{bigint::Integer, util, wes19};
 
 // FOR BENCHMARKING ONLY
 // NO SECURITY CHECK
@@ -31,7 +30,7 @@ pub fn verify(
     let g_r = g.clone().pow_mod(&r, modulus).unwrap();
     let pi_l_g_r = pi_l * g_r;
 
-    Integer::from(pi_l_g_r.div_rem_floor(modulus.clone()).1) == y.clone()
+    pi_l_g_r.div_rem_floor(modulus.clone()).1 == y.clone()
 }
 
 fn bench_wes19(c: &mut Criterion) {
@@ -43,7 +42,7 @@ fn bench_wes19(c: &mut Criterion) {
                        target: &Integer| {
         c.bench_function(
             &format!("wes19::solve() with num_steps {}", num_steps),
-            move |b| b.iter(|| wes19::solve(modulus, state, num_steps, pubkey, target)),
+            move |b| b.iter(|| wes19::solve(modulus, state, num_steps, num_steps, pubkey, target)),
         );
     };
     let bench_prove =
@@ -84,13 +83,13 @@ fn bench_wes19(c: &mut Criterion) {
     const TARGET_HASH: &str = "07fb30c7163271850b6d018e8282093ac6755a771da6267edf6c9b4fce9242ba";
 
     let seed_hash = Integer::from_str_radix(PREV_BLOCK_HASH, 16).unwrap();
-    let seed = Integer::from(seed_hash.div_rem_floor(modulus.clone()).1);
+    let seed = seed_hash.div_rem_floor(modulus.clone()).1;
     println!("seed:\t\t0x{:064x}", seed);
 
     let target_hash = Integer::from_str_radix(TARGET_HASH, 16).unwrap();
-    let target = Integer::from(target_hash.div_rem_floor(modulus.clone()).1);
+    let target = target_hash.div_rem_floor(modulus.clone()).1;
     println!("target:\t\t0x{:064x}", target);
-    println!("");
+    println!();
 
     let (_, pubkey) = ecvrf::keygen();
     let g = util::h_g(&modulus, &pubkey, &seed);
@@ -106,6 +105,18 @@ fn bench_wes19(c: &mut Criterion) {
         bench_prove(c, num_steps, &modulus, &g, &y);
         bench_verify(c, num_steps, &modulus, &g, &y, &pi, &pubkey, &target);
     }
+
+    // fixed-base table size against proving time, 2 entries is the plain long division
+    let num_steps = 64_000;
+    for &max_table_entries in &[2, 16, 256, 4096] {
+        c.bench_function(
+            &format!(
+                "wes19::prove_with_table() with num_steps {} and {} table entries",
+                num_steps, max_table_entries
+            ),
+            |b| b.iter(|| wes19::prove_with_table(&modulus, &g, num_steps, &y, max_table_entries)),
+        );
+    }
 }
 
 criterion_group! {
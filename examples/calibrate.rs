@@ -0,0 +1,63 @@
+use seq_pow::{
+    bigint::Integer,
+    calibrate,
+    scheme::{Pie19, Sloth, Wes19},
+};
+use std::env;
+use std::time::Duration;
+
+/// RSA-2048 modulus, taken from [Wikipedia](https://en.wikipedia.org/wiki/RSA_numbers#RSA-2048).
+pub const RSA_MODULUS: &str =
+  "251959084756578934940271832400483985714292821262040320277771378360436620207075955562640185258807\
+  8440691829064124951508218929855914917618450280848912007284499268739280728777673597141834727026189\
+  6375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172\
+  6546322822168699875491824224336372590851418654620435767984233871847744479207399342365848238242811\
+  9816381501067481045166037730605620161967625613384414360383390441495263443219011465754445417842402\
+  0924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951\
+  378636564391212010397122822120720357";
+
+/// Prime modulus for sloth (M13 prime), see https://www.rieselprime.de/ziki/List_of_known_Mersenne_primes
+pub const PRIME_MODULUS: &str = "6864797660130609714981900799081393217269435300143305409394463459185543183397656052122559640661454554977296311391480858037121987999716643812574028291115057151";
+
+/// Measures this machine and recommends mining parameters for each scheme.
+///
+/// Usage: seqpow-calibrate [block interval in seconds, default 600] [measuring seconds per scheme,
+/// default 2]
+fn main() {
+    let mut args = env::args().skip(1);
+    let block_interval = args.next().map_or(600.0, |a| a.parse::<f64>().unwrap());
+    let measure_secs = args.next().map_or(2.0, |a| a.parse::<f64>().unwrap());
+    let block_interval = Duration::from_secs_f64(block_interval);
+    let measure = Duration::from_secs_f64(measure_secs);
+
+    let rsa_modulus = Integer::from_str_radix(RSA_MODULUS, 10).unwrap();
+    let prime_modulus = Integer::from_str_radix(PRIME_MODULUS, 10).unwrap();
+    let (_, pubkey) = ecvrf::keygen();
+
+    println!("block interval:\t{:?}", block_interval);
+    println!();
+    let calibrations = vec![
+        (
+            "sloth",
+            calibrate::calibrate(&Sloth, &prime_modulus, &pubkey, measure),
+        ),
+        (
+            "wes19",
+            calibrate::calibrate(&Wes19, &rsa_modulus, &pubkey, measure),
+        ),
+        (
+            "pie19",
+            calibrate::calibrate(&Pie19, &rsa_modulus, &pubkey, measure),
+        ),
+    ];
+    for (name, calibration) in calibrations {
+        let recommendation = calibration.recommend(block_interval);
+        println!("{}:", name);
+        println!("  squarings/s:\t{:.0}", calibration.squarings_per_second);
+        println!("  checks/s:\t{:.0}", calibration.checks_per_second);
+        println!("  step:\t\t{}", recommendation.step);
+        println!("  check interval:\t{}", recommendation.check_interval);
+        println!("  target:\t0x{:064x}", recommendation.target);
+        println!("  expected T:\t{}", recommendation.expected_iterations);
+    }
+}
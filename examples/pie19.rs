@@ -1,7 +1,5 @@
-use ecvrf;
 use elapsed::measure_time;
-use rug::Integer;
-use seq_pow::{pie19, util};
+use seq_pow::{bigint::Integer, limits::VerifyLimits, pie19, util};
 
 /// RSA-2048 modulus, taken from [Wikipedia](https://en.wikipedia.org/wiki/RSA_numbers#RSA-2048).
 pub const MODULUS: &str =
@@ -22,33 +20,55 @@ fn main() {
         "1eeb30c7163271850b6d018e8282093ac6755a771da6267edf6c9b4fce9242ba";
     const TARGET_HASH: &str = "07fb30c7163271850b6d018e8282093ac6755a771da6267edf6c9b4fce9242ba";
     const NUM_STEPS: u64 = 10;
+    // the difficulty is checked every CHECK_INTERVAL steps, miner and verifier must agree on it
+    const CHECK_INTERVAL: u64 = 2;
+    // leave up to 2^DEPTH squarings to the verifier instead of proving them
+    const DEPTH: u32 = 4;
 
     let seed_hash = Integer::from_str_radix(PREV_BLOCK_HASH, 16).unwrap();
-    let seed = Integer::from(seed_hash.div_rem_floor(modulus.clone()).1);
+    let seed = seed_hash.div_rem_floor(modulus.clone()).1;
     println!("seed:\t\t0x{:064x}", seed);
 
     let target_hash = Integer::from_str_radix(TARGET_HASH, 16).unwrap();
-    let target = Integer::from(target_hash.div_rem_floor(modulus.clone()).1);
+    let target = target_hash.div_rem_floor(modulus.clone()).1;
     println!("target:\t\t0x{:064x}", target);
-    println!("");
+    println!();
 
     let (_, pubkey) = ecvrf::keygen();
     let g = util::h_g(&modulus, &pubkey, &seed);
 
     println!("start mining...");
-    let (elapsed, (y, pi_list, iters)) =
-        measure_time(|| pie19::mine(NUM_STEPS, &pubkey, &modulus, &g, &target));
+    let ((y, proof, iters), report) = pie19::mine_with_report(
+        NUM_STEPS,
+        CHECK_INTERVAL,
+        DEPTH,
+        &pubkey,
+        &modulus,
+        &g,
+        &target,
+    );
     println!("found nonce:\t{}", iters);
     println!("y:\t0x{:x}", &y);
     // println!("pi:\t0x{:x}", &pi);
-    println!("elapsed:\t{}", elapsed);
-    println!("");
+    println!("report:\t{}", report);
+    println!();
 
     println!("verifying SeqPoW proof...");
-    let (elapsed, is_verified) =
-        measure_time(|| pie19::verify(&modulus, &g, &y, iters, &pi_list, &pubkey, &target));
-    println!("verified:\t{}", is_verified);
+    let (elapsed, is_verified) = measure_time(|| {
+        pie19::verify(
+            &modulus,
+            &g,
+            &y,
+            iters,
+            CHECK_INTERVAL,
+            &proof,
+            &pubkey,
+            &target,
+            &VerifyLimits::default(),
+        )
+    });
+    println!("verified:\t{:?}", is_verified);
     println!("elapsed:\t{}", elapsed);
 
-    assert!(is_verified)
+    assert_eq!(is_verified, Ok(()))
 }
@@ -0,0 +1,392 @@
+use seq_pow::{
+    bigint::Integer,
+    calibrate,
+    chain::{self, BlockTree},
+    difficulty,
+    scheme::{Pie19, Scheme, Sloth, Wes19},
+    stream::MiningStream,
+    util::{self, SplitMix64},
+};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::env;
+use std::time::Duration;
+
+/// RSA-2048 modulus, taken from [Wikipedia](https://en.wikipedia.org/wiki/RSA_numbers#RSA-2048).
+pub const RSA_MODULUS: &str =
+  "251959084756578934940271832400483985714292821262040320277771378360436620207075955562640185258807\
+  8440691829064124951508218929855914917618450280848912007284499268739280728777673597141834727026189\
+  6375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172\
+  6546322822168699875491824224336372590851418654620435767984233871847744479207399342365848238242811\
+  9816381501067481045166037730605620161967625613384414360383390441495263443219011465754445417842402\
+  0924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951\
+  378636564391212010397122822120720357";
+
+/// Prime modulus for sloth (M13 prime), see https://www.rieselprime.de/ziki/List_of_known_Mersenne_primes
+pub const PRIME_MODULUS: &str = "6864797660130609714981900799081393217269435300143305409394463459185543183397656052122559640661454554977296311391480858037121987999716643812574028291115057151";
+
+/// Seconds spent measuring this machine before simulating.
+const MEASURE_SECS: f64 = 1.0;
+
+struct Config {
+    real: bool,
+    miners: usize,
+    blocks: usize,
+    block_interval: f64,
+    max_delay: f64,
+    speed_spread: f64,
+    seed: u64,
+}
+
+struct Miner {
+    pubkey: ecvrf::VrfPk,
+    /// Iterations per simulated second.
+    speed: f64,
+    /// Seconds for a block to reach or leave this miner.
+    delay: f64,
+    tree: BlockTree<u64>,
+    /// Bumped on every restart, so that `Found` events of abandoned attempts are dropped.
+    attempt: u64,
+    /// Parent, iterations and, in real mode, final state of the current attempt.
+    pending: (u64, u64, Option<Integer>),
+}
+
+struct Block {
+    miner: Option<usize>,
+    parent: Option<u64>,
+    iters: u64,
+    state: Option<Integer>,
+    time: f64,
+}
+
+enum Kind {
+    Found { miner: usize, attempt: u64 },
+    Arrive { miner: usize, hash: u64 },
+}
+
+struct Event {
+    time: f64,
+    seq: u64,
+    kind: Kind,
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// reversed, so that the max-heap pops the earliest event first
+impl Ord for Event {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .time
+            .total_cmp(&self.time)
+            .then(other.seq.cmp(&self.seq))
+    }
+}
+
+/// A discrete-event simulation of miners racing on one block tree. Every miner keeps its own
+/// view of the tree and mines on its best tip; a block reaches another miner after both their
+/// delays.
+struct Sim<'a, S> {
+    scheme: S,
+    modulus: &'a Integer,
+    real: bool,
+    step: u64,
+    check_interval: u64,
+    target: Integer,
+    rng: SplitMix64,
+    miners: Vec<Miner>,
+    blocks: HashMap<u64, Block>,
+    tree: BlockTree<u64>,
+    events: BinaryHeap<Event>,
+    seq: u64,
+}
+
+impl<'a, S: Scheme + Copy> Sim<'a, S> {
+    fn push(&mut self, time: f64, kind: Kind) {
+        self.seq += 1;
+        self.events.push(Event {
+            time,
+            seq: self.seq,
+            kind,
+        });
+    }
+
+    /// Iterations until `miner` solves on top of `parent`, and the solution in real mode.
+    fn solve(&mut self, miner: usize, parent: u64) -> (u64, Option<Integer>) {
+        if !self.real {
+            // the number of checks until the first success is geometric
+            let p = difficulty::success_probability(&self.target);
+            let u = 1.0 - self.rng.next_f64();
+            let checks = (u.ln() / (-p).ln_1p()).ceil().max(1.0);
+            return ((checks as u64).saturating_mul(self.check_interval), None);
+        }
+
+        let seed = match &self.blocks[&parent].state {
+            Some(state) => chain::next_seed(state),
+            None => Integer::from(parent),
+        };
+        let pubkey = &self.miners[miner].pubkey;
+        let ini_state = util::h_g(self.modulus, pubkey, &seed);
+        let (state, iters, _) = MiningStream::new(
+            self.scheme,
+            self.step,
+            self.check_interval,
+            pubkey,
+            self.modulus,
+            &ini_state,
+            &self.target,
+        )
+        .last()
+        .unwrap();
+        (iters, Some(state))
+    }
+
+    /// Starts `miner` on its best tip at `now`.
+    fn restart(&mut self, miner: usize, now: f64) {
+        let parent = *self.miners[miner].tree.best_tip().unwrap();
+        let (iters, state) = self.solve(miner, parent);
+        let m = &mut self.miners[miner];
+        m.attempt += 1;
+        m.pending = (parent, iters, state);
+        let (attempt, finish) = (m.attempt, now + iters as f64 / m.speed);
+        self.push(finish, Kind::Found { miner, attempt });
+    }
+
+    /// Adds `hash` to the view of `miner` and restarts it if its best tip changed.
+    fn receive(&mut self, miner: usize, hash: u64, now: f64) {
+        let block = &self.blocks[&hash];
+        let tree = &mut self.miners[miner].tree;
+        let best = tree.best_tip().copied();
+        tree.insert(hash, block.parent, &self.target, block.iters)
+            .unwrap();
+        if tree.best_tip().copied() != best {
+            self.restart(miner, now);
+        }
+    }
+
+    /// Runs until `blocks` blocks are found and returns the time of the last one.
+    fn run(&mut self, blocks: usize) -> f64 {
+        for miner in 0..self.miners.len() {
+            self.restart(miner, 0.0);
+        }
+
+        let mut found = 0;
+        while let Some(event) = self.events.pop() {
+            match event.kind {
+                Kind::Found { miner, attempt } => {
+                    if attempt != self.miners[miner].attempt {
+                        continue;
+                    }
+                    let (parent, iters, state) = self.miners[miner].pending.clone();
+                    let hash = self.rng.next_u64();
+                    self.blocks.insert(
+                        hash,
+                        Block {
+                            miner: Some(miner),
+                            parent: Some(parent),
+                            iters,
+                            state,
+                            time: event.time,
+                        },
+                    );
+                    self.tree
+                        .insert(hash, Some(parent), &self.target, iters)
+                        .unwrap();
+                    found += 1;
+                    if found == blocks {
+                        return event.time;
+                    }
+
+                    for other in 0..self.miners.len() {
+                        if other != miner {
+                            let delay = self.miners[miner].delay + self.miners[other].delay;
+                            self.push(event.time + delay, Kind::Arrive { miner: other, hash });
+                        }
+                    }
+                    self.receive(miner, hash, event.time);
+                }
+                Kind::Arrive { miner, hash } => self.receive(miner, hash, event.time),
+            }
+        }
+        unreachable!("miners always have a pending attempt")
+    }
+}
+
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * q).round() as usize]
+}
+
+fn simulate<S: Scheme + Copy>(scheme: S, modulus: &Integer, config: &Config) {
+    let (_, pubkey) = ecvrf::keygen();
+    let measure = Duration::from_secs_f64(MEASURE_SECS);
+    let calibration = calibrate::calibrate(&scheme, modulus, &pubkey, measure);
+    // every miner on its own takes `miners` block intervals, so the network takes about one
+    let per_miner = config.block_interval * config.miners as f64;
+    let recommendation = calibration.recommend(Duration::from_secs_f64(per_miner));
+    let base_speed = recommendation.expected_iterations as f64 / per_miner;
+
+    let mut rng = SplitMix64::new(config.seed);
+    let genesis = rng.next_u64();
+    let target = recommendation.target.clone();
+    // relative speeds averaging 1, so the network as a whole keeps the block interval
+    let factors: Vec<f64> = (0..config.miners)
+        .map(|_| 1.0 + config.speed_spread * rng.next_f64())
+        .collect();
+    let mean_factor = factors.iter().sum::<f64>() / factors.len() as f64;
+    let mut miners = Vec::new();
+    for factor in factors {
+        let mut tree = BlockTree::new();
+        tree.insert(genesis, None, &target, 0).unwrap();
+        miners.push(Miner {
+            pubkey: ecvrf::keygen().1,
+            speed: base_speed * factor / mean_factor,
+            delay: config.max_delay * rng.next_f64(),
+            tree,
+            attempt: 0,
+            pending: (genesis, 0, None),
+        });
+    }
+    let mut blocks = HashMap::new();
+    blocks.insert(
+        genesis,
+        Block {
+            miner: None,
+            parent: None,
+            iters: 0,
+            state: None,
+            time: 0.0,
+        },
+    );
+    let mut tree = BlockTree::new();
+    tree.insert(genesis, None, &target, 0).unwrap();
+
+    println!("squarings/s:\t{:.0}", calibration.squarings_per_second);
+    println!("check interval:\t{}", recommendation.check_interval);
+    println!("target:\t\t0x{:064x}", target);
+    println!("expected T:\t{}", recommendation.expected_iterations);
+    println!();
+
+    let mut sim = Sim {
+        scheme,
+        modulus,
+        real: config.real,
+        step: recommendation.step,
+        check_interval: recommendation.check_interval,
+        target,
+        rng,
+        miners,
+        blocks,
+        tree,
+        events: BinaryHeap::new(),
+        seq: 0,
+    };
+    let elapsed = sim.run(config.blocks);
+
+    let tip = *sim.tree.best_tip().unwrap();
+    let mut main_chain: Vec<u64> = sim.tree.ancestors(&tip).copied().collect();
+    main_chain.reverse();
+    let mined = sim.blocks.len() - 1;
+    let canonical = main_chain.len() - 1;
+    println!("simulated:\t{:.1}s", elapsed);
+    println!("blocks:\t\t{}", mined);
+    println!("main chain:\t{}", canonical);
+    println!(
+        "orphan rate:\t{:.2}%",
+        100.0 * (mined - canonical) as f64 / mined as f64
+    );
+
+    let times: Vec<f64> = main_chain.iter().map(|h| sim.blocks[h].time).collect();
+    let mut intervals: Vec<f64> = times.windows(2).map(|w| w[1] - w[0]).collect();
+    if !intervals.is_empty() {
+        intervals.sort_by(f64::total_cmp);
+        let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+        let var = intervals
+            .iter()
+            .map(|x| (x - mean) * (x - mean))
+            .sum::<f64>()
+            / intervals.len() as f64;
+        println!("block interval:");
+        println!("  mean:\t\t{:.3}s", mean);
+        println!("  stddev:\t{:.3}s", var.sqrt());
+        for q in &[0.1, 0.5, 0.9, 0.99] {
+            println!("  p{}:\t\t{:.3}s", q * 100.0, percentile(&intervals, *q));
+        }
+    }
+
+    let total_speed: f64 = sim.miners.iter().map(|m| m.speed).sum();
+    println!("miners:");
+    println!("  #\tspeed\tdelay\tmined\tmain\tshare\tfairness");
+    for (i, miner) in sim.miners.iter().enumerate() {
+        let mined = sim.blocks.values().filter(|b| b.miner == Some(i)).count();
+        let main = main_chain
+            .iter()
+            .filter(|h| sim.blocks[*h].miner == Some(i))
+            .count();
+        let share = main as f64 / canonical.max(1) as f64;
+        let speed_share = miner.speed / total_speed;
+        println!(
+            "  {}\t{:.2}\t{:.3}s\t{}\t{}\t{:.3}\t{:.2}",
+            i,
+            miner.speed / base_speed,
+            miner.delay,
+            mined,
+            main,
+            share,
+            share / speed_share
+        );
+    }
+}
+
+/// Simulates miners with different speeds and propagation delays racing on one chain, and
+/// reports the orphan rate, the block interval distribution and each miner's share of the main
+/// chain relative to its share of the speed (fairness 1.0 is proportional).
+///
+/// In `model` mode the iterations of each attempt are drawn from the distribution the target
+/// gives; in `real` mode every attempt is mined with the scheme, so keep the block interval short.
+///
+/// Usage: seqpow-sim [sloth|wes19|pie19, default wes19] [model|real, default model] [miners,
+/// default 8] [blocks, default 200] [block interval in seconds, default 600] [max delay in
+/// seconds, default 6] [speed spread, default 1] [rng seed, default 1]
+fn main() {
+    let mut args = env::args().skip(1);
+    let scheme = args.next().unwrap_or_else(|| "wes19".to_string());
+    let mode = args.next().unwrap_or_else(|| "model".to_string());
+    let config = Config {
+        real: match mode.as_str() {
+            "model" => false,
+            "real" => true,
+            _ => panic!("unknown mode {}", mode),
+        },
+        miners: args.next().map_or(8, |a| a.parse().unwrap()),
+        blocks: args.next().map_or(200, |a| a.parse().unwrap()),
+        block_interval: args.next().map_or(600.0, |a| a.parse().unwrap()),
+        max_delay: args.next().map_or(6.0, |a| a.parse().unwrap()),
+        speed_spread: args.next().map_or(1.0, |a| a.parse().unwrap()),
+        seed: args.next().map_or(1, |a| a.parse().unwrap()),
+    };
+    assert!(config.miners > 0 && config.blocks > 0);
+
+    let rsa_modulus = Integer::from_str_radix(RSA_MODULUS, 10).unwrap();
+    let prime_modulus = Integer::from_str_radix(PRIME_MODULUS, 10).unwrap();
+    println!("scheme:\t\t{} ({})", scheme, mode);
+    println!("miners:\t\t{}", config.miners);
+    println!("block interval:\t{}s", config.block_interval);
+    println!("max delay:\t{}s", config.max_delay);
+    match scheme.as_str() {
+        "sloth" => simulate(Sloth, &prime_modulus, &config),
+        "wes19" => simulate(Wes19, &rsa_modulus, &config),
+        "pie19" => simulate(Pie19, &rsa_modulus, &config),
+        _ => panic!("unknown scheme {}", scheme),
+    }
+}
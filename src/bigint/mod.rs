@@ -0,0 +1,18 @@
+//! Arbitrary-precision integer backend.
+//!
+//! With the default `gmp` feature this is `rug::Integer` (GMP, needs a C toolchain, LGPL).
+//! With `--no-default-features --features pure-rust` a `num-bigint` based type exposing the same
+//! API is used instead, for static, audited or `wasm32` builds.
+//!
+//! With both features `gmp` is used.
+
+#[cfg(not(any(feature = "gmp", feature = "pure-rust")))]
+compile_error!("enable one of the `gmp` or `pure-rust` features to select a big-integer backend");
+
+#[cfg(feature = "gmp")]
+pub use rug::{integer::Order, Assign, Integer};
+
+#[cfg(not(feature = "gmp"))]
+mod pure;
+#[cfg(not(feature = "gmp"))]
+pub use self::pure::{Assign, Integer, Order};
@@ -0,0 +1,402 @@
+/// Pure-Rust arbitrary-precision integer on top of `num-bigint`.
+///
+/// Mirrors the subset of the `rug::Integer` API used by this crate so that the schemes compile
+/// unchanged against either backend.
+use num_bigint::{BigInt, Sign};
+use num_integer::Integer as _;
+use num_traits::{Num, One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Shl, ShlAssign, Shr,
+    ShrAssign, Sub, SubAssign,
+};
+
+/// Digit order, as in `rug::integer::Order`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// Least significant digit first.
+    Lsf,
+    /// Most significant digit first.
+    Msf,
+}
+
+/// Assignment without reallocation, as in `rug::Assign`.
+pub trait Assign<Src = Self> {
+    fn assign(&mut self, src: Src);
+}
+
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Integer(BigInt);
+
+// Small primes for trial division before Miller-Rabin, the first 25 also serve as witnesses.
+const SMALL_PRIMES: [u32; 54] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+const MILLER_RABIN_ROUNDS: usize = 25;
+
+impl Integer {
+    pub fn new() -> Self {
+        Integer(BigInt::zero())
+    }
+
+    pub fn from_str_radix(src: &str, radix: i32) -> Result<Self, num_bigint::ParseBigIntError> {
+        <BigInt as Num>::from_str_radix(src, radix as u32).map(Integer)
+    }
+
+    /// Absolute value as digits, zero has no digits.
+    pub fn to_digits<T: From<u8>>(&self, order: Order) -> Vec<T> {
+        if self.0.is_zero() {
+            return Vec::new();
+        }
+        let bytes = match order {
+            Order::Lsf => self.0.magnitude().to_bytes_le(),
+            Order::Msf => self.0.magnitude().to_bytes_be(),
+        };
+        bytes.into_iter().map(T::from).collect()
+    }
+
+    pub fn from_digits(digits: &[u8], order: Order) -> Self {
+        match order {
+            Order::Lsf => Integer(BigInt::from_bytes_le(Sign::Plus, digits)),
+            Order::Msf => Integer(BigInt::from_bytes_be(Sign::Plus, digits)),
+        }
+    }
+
+    pub fn significant_bits(&self) -> u32 {
+        self.0.bits() as u32
+    }
+
+    pub fn get_bit(&self, index: u32) -> bool {
+        self.0.bit(u64::from(index))
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.0.is_odd()
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.0.is_even()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        self.0.to_u64()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.0.to_f64().unwrap_or(f64::INFINITY)
+    }
+
+    pub fn square_mut(&mut self) {
+        self.0 = &self.0 * &self.0;
+    }
+
+    pub fn div_rem_floor(self, divisor: Self) -> (Self, Self) {
+        let (q, r) = self.0.div_mod_floor(&divisor.0);
+        (Integer(q), Integer(r))
+    }
+
+    pub fn gcd(self, other: &Self) -> Self {
+        Integer(self.0.gcd(&other.0))
+    }
+
+    pub fn invert(self, modulo: &Self) -> Result<Self, Self> {
+        if modulo.0.is_zero() {
+            return Err(self);
+        }
+        match self.0.modinv(&modulo.0) {
+            Some(inverse) => Ok(Integer(inverse.mod_floor(&modulo.0.abs()))),
+            None => Err(self),
+        }
+    }
+
+    pub fn pow_mod(mut self, exponent: &Self, modulo: &Self) -> Result<Self, Self> {
+        match self.pow_mod_mut(exponent, modulo) {
+            Ok(()) => Ok(self),
+            Err(()) => Err(self),
+        }
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn pow_mod_mut(&mut self, exponent: &Self, modulo: &Self) -> Result<(), ()> {
+        if modulo.0.is_zero() {
+            return Err(());
+        }
+        let modulo = modulo.0.abs();
+        if exponent.0.is_negative() {
+            let inverse = self.0.modinv(&modulo).ok_or(())?;
+            self.0 = inverse.modpow(&-&exponent.0, &modulo);
+        } else {
+            self.0 = self.0.modpow(&exponent.0, &modulo);
+        }
+        Ok(())
+    }
+
+    /// Jacobi symbol (self / n) for an odd positive `n`.
+    pub fn jacobi(&self, n: &Self) -> i32 {
+        let mut a = self.0.mod_floor(&n.0);
+        let mut n = n.0.clone();
+        let mut result = 1;
+        while !a.is_zero() {
+            // (2 / n) = -1 exactly for n = 3, 5 mod 8
+            let twos = a.trailing_zeros().unwrap_or(0);
+            a >>= twos;
+            let n_mod_8 = (&n % 8u32).to_u32().unwrap();
+            if twos % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+                result = -result;
+            }
+            // quadratic reciprocity flips the sign when both are 3 mod 4
+            if (&a % 4u32).to_u32() == Some(3) && n_mod_8 % 4 == 3 {
+                result = -result;
+            }
+            std::mem::swap(&mut a, &mut n);
+            a = a.mod_floor(&n);
+        }
+        if n.is_one() {
+            result
+        } else {
+            0
+        }
+    }
+
+    pub fn next_prime(self) -> Self {
+        let two = BigInt::from(2);
+        if self.0 < two {
+            return Integer(two);
+        }
+        let mut candidate = &self.0 + 1u32;
+        if candidate == BigInt::from(3) {
+            return Integer(candidate);
+        }
+        if candidate.is_even() {
+            candidate += 1u32;
+        }
+        while !is_probably_prime(&candidate) {
+            candidate += 2u32;
+        }
+        Integer(candidate)
+    }
+}
+
+fn is_probably_prime(n: &BigInt) -> bool {
+    for &p in SMALL_PRIMES.iter() {
+        let p = BigInt::from(p);
+        if *n == p {
+            return true;
+        }
+        if (n % &p).is_zero() {
+            return false;
+        }
+    }
+
+    // n - 1 = d * 2^s with d odd
+    let n_minus_one = n - 1u32;
+    let s = n_minus_one.trailing_zeros().unwrap_or(0);
+    let d = &n_minus_one >> s;
+
+    'witness: for &a in SMALL_PRIMES.iter().take(MILLER_RABIN_ROUNDS) {
+        let mut x = BigInt::from(a).modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = (&x * &x) % n;
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Debug for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::LowerHex for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+impl Assign for Integer {
+    fn assign(&mut self, src: Integer) {
+        self.0 = src.0;
+    }
+}
+
+impl<'a> Assign<&'a Integer> for Integer {
+    fn assign(&mut self, src: &'a Integer) {
+        self.0.clone_from(&src.0);
+    }
+}
+
+impl Neg for Integer {
+    type Output = Integer;
+    fn neg(self) -> Integer {
+        Integer(-self.0)
+    }
+}
+
+impl Neg for &Integer {
+    type Output = Integer;
+    fn neg(self) -> Integer {
+        Integer(-&self.0)
+    }
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Integer {
+            fn from(value: $t) -> Self {
+                Integer(BigInt::from(value))
+            }
+        }
+
+        impl PartialEq<$t> for Integer {
+            fn eq(&self, other: &$t) -> bool {
+                self.0 == BigInt::from(*other)
+            }
+        }
+
+        impl PartialOrd<$t> for Integer {
+            fn partial_cmp(&self, other: &$t) -> Option<Ordering> {
+                self.0.partial_cmp(&BigInt::from(*other))
+            }
+        }
+    )*};
+}
+
+impl_primitive!(i32, i64, u32, u64, usize);
+
+macro_rules! impl_arith {
+    ($($trait:ident $method:ident $assign_trait:ident $assign_method:ident;)*) => {$(
+        impl $trait<Integer> for Integer {
+            type Output = Integer;
+            fn $method(self, rhs: Integer) -> Integer {
+                Integer($trait::$method(self.0, rhs.0))
+            }
+        }
+
+        impl<'a> $trait<&'a Integer> for Integer {
+            type Output = Integer;
+            fn $method(self, rhs: &'a Integer) -> Integer {
+                Integer($trait::$method(self.0, &rhs.0))
+            }
+        }
+
+        impl<'a> $trait<Integer> for &'a Integer {
+            type Output = Integer;
+            fn $method(self, rhs: Integer) -> Integer {
+                Integer($trait::$method(&self.0, rhs.0))
+            }
+        }
+
+        impl<'a, 'b> $trait<&'b Integer> for &'a Integer {
+            type Output = Integer;
+            fn $method(self, rhs: &'b Integer) -> Integer {
+                Integer($trait::$method(&self.0, &rhs.0))
+            }
+        }
+
+        impl $assign_trait<Integer> for Integer {
+            fn $assign_method(&mut self, rhs: Integer) {
+                $assign_trait::$assign_method(&mut self.0, rhs.0);
+            }
+        }
+
+        impl<'a> $assign_trait<&'a Integer> for Integer {
+            fn $assign_method(&mut self, rhs: &'a Integer) {
+                $assign_trait::$assign_method(&mut self.0, &rhs.0);
+            }
+        }
+
+        impl_arith!(@primitive $trait $method $assign_trait $assign_method; i32, i64, u32, u64);
+    )*};
+    (@primitive $trait:ident $method:ident $assign_trait:ident $assign_method:ident; $($t:ty),*) => {$(
+        impl $trait<$t> for Integer {
+            type Output = Integer;
+            fn $method(self, rhs: $t) -> Integer {
+                Integer($trait::$method(self.0, BigInt::from(rhs)))
+            }
+        }
+
+        impl<'a> $trait<$t> for &'a Integer {
+            type Output = Integer;
+            fn $method(self, rhs: $t) -> Integer {
+                Integer($trait::$method(&self.0, BigInt::from(rhs)))
+            }
+        }
+
+        impl $assign_trait<$t> for Integer {
+            fn $assign_method(&mut self, rhs: $t) {
+                $assign_trait::$assign_method(&mut self.0, BigInt::from(rhs));
+            }
+        }
+    )*};
+}
+
+impl_arith! {
+    Add add AddAssign add_assign;
+    Sub sub SubAssign sub_assign;
+    Mul mul MulAssign mul_assign;
+    Div div DivAssign div_assign;
+    Rem rem RemAssign rem_assign;
+}
+
+impl Shl<u32> for Integer {
+    type Output = Integer;
+    fn shl(self, rhs: u32) -> Integer {
+        Integer(self.0 << rhs)
+    }
+}
+
+impl Shr<u32> for Integer {
+    type Output = Integer;
+    fn shr(self, rhs: u32) -> Integer {
+        Integer(self.0 >> rhs)
+    }
+}
+
+impl Shl<u32> for &Integer {
+    type Output = Integer;
+    fn shl(self, rhs: u32) -> Integer {
+        Integer(&self.0 << rhs)
+    }
+}
+
+impl Shr<u32> for &Integer {
+    type Output = Integer;
+    fn shr(self, rhs: u32) -> Integer {
+        Integer(&self.0 >> rhs)
+    }
+}
+
+impl ShlAssign<u32> for Integer {
+    fn shl_assign(&mut self, rhs: u32) {
+        self.0 <<= rhs;
+    }
+}
+
+impl ShrAssign<u32> for Integer {
+    fn shr_assign(&mut self, rhs: u32) {
+        self.0 >>= rhs;
+    }
+}
@@ -0,0 +1,181 @@
+/// Measuring a machine's squaring and hashing speed to pick mining parameters.
+use super::bigint::Integer;
+use super::scheme::Scheme;
+use super::util;
+use ecvrf;
+use std::time::{Duration, Instant};
+
+// squarings per timed `solve` call while measuring
+const MEASURE_BATCH: u64 = 256;
+// largest share of the mining time spent on difficulty checks
+const MAX_CHECK_OVERHEAD: f64 = 0.01;
+// wall-clock length of a recommended `step`
+const STEP_DURATION: Duration = Duration::from_millis(10);
+
+/// Measured speeds of one scheme on this machine.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    /// Squarings (square roots for sloth) per second in `solve`.
+    pub squarings_per_second: f64,
+    /// `h_state` and `validate_difficulty` checks per second.
+    pub checks_per_second: f64,
+}
+
+/// Mining parameters for a block interval.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recommendation {
+    /// Iterations per `solve` call, about 10ms of squaring.
+    pub step: u64,
+    /// Smallest check interval keeping the checks under 1% of the mining time.
+    pub check_interval: u64,
+    pub target: Integer,
+    /// Expected iterations until a state meets `target`.
+    pub expected_iterations: u64,
+}
+
+/// Runs `scheme`'s `solve` for about `duration`, with a target no state meets.
+pub fn squarings_per_second<S: Scheme>(
+    scheme: &S,
+    modulus: &Integer,
+    pubkey: &ecvrf::VrfPk,
+    duration: Duration,
+) -> f64 {
+    let target = Integer::new();
+    let mut state = util::h_g(modulus, pubkey, &Integer::from(1));
+    let mut squarings = 0;
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let (new_state, done, _) = scheme.solve(
+            modulus,
+            &state,
+            MEASURE_BATCH,
+            MEASURE_BATCH,
+            pubkey,
+            &target,
+        );
+        state = new_state;
+        squarings += done;
+    }
+    squarings as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Runs difficulty checks of states modulo `modulus` for about `duration`.
+pub fn checks_per_second(modulus: &Integer, pubkey: &ecvrf::VrfPk, duration: Duration) -> f64 {
+    let target = Integer::new();
+    let mut state = util::h_g(modulus, pubkey, &Integer::from(1));
+    let mut checks = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let hstate = util::h_state(modulus, pubkey, &state);
+        util::validate_difficulty(&hstate, &target);
+        state += 1;
+        checks += 1;
+    }
+    checks as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Measures `scheme` for about `duration` in total.
+pub fn calibrate<S: Scheme>(
+    scheme: &S,
+    modulus: &Integer,
+    pubkey: &ecvrf::VrfPk,
+    duration: Duration,
+) -> Calibration {
+    Calibration {
+        squarings_per_second: squarings_per_second(scheme, modulus, pubkey, duration / 2),
+        checks_per_second: checks_per_second(modulus, pubkey, duration / 2),
+    }
+}
+
+impl Calibration {
+    /// Parameters under which mining takes `block_interval` on average on this machine.
+    ///
+    /// A state is checked every `check_interval` iterations and meets the target with
+    /// probability (target + 1) / 2^256, so the target is set to make that
+    /// check_interval / expected_iterations.
+    pub fn recommend(&self, block_interval: Duration) -> Recommendation {
+        let check_interval =
+            (self.squarings_per_second / (self.checks_per_second * MAX_CHECK_OVERHEAD)).ceil();
+        let check_interval = (check_interval as u64).max(1);
+
+        let secs_per_iteration = 1.0 / self.squarings_per_second
+            + 1.0 / (self.checks_per_second * check_interval as f64);
+        let expected_iterations = (block_interval.as_secs_f64() / secs_per_iteration) as u64;
+        let expected_iterations = expected_iterations.max(check_interval);
+
+        let step = (self.squarings_per_second * STEP_DURATION.as_secs_f64()) as u64;
+        let step = (step / check_interval).max(1) * check_interval;
+
+        let target = (Integer::from(1) << 256u32) * check_interval / expected_iterations - 1;
+
+        Recommendation {
+            step,
+            check_interval,
+            target,
+            expected_iterations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::difficulty;
+    use super::*;
+
+    fn assert_consistent(recommendation: &Recommendation) {
+        let Recommendation {
+            step,
+            check_interval,
+            target,
+            expected_iterations,
+        } = recommendation;
+        assert!(*step >= *check_interval);
+        assert_eq!(step % check_interval, 0);
+        assert!(expected_iterations >= check_interval);
+        let expected = difficulty::expected_iterations(target, *check_interval);
+        let error = (expected - *expected_iterations as f64).abs() / expected;
+        assert!(error < 1e-9, "{} against {}", expected, expected_iterations);
+    }
+
+    #[test]
+    fn recommends_parameters_for_the_block_interval() {
+        let calibration = Calibration {
+            squarings_per_second: 1_000_000.0,
+            checks_per_second: 200_000.0,
+        };
+        let recommendation = calibration.recommend(Duration::from_secs(10));
+        assert_consistent(&recommendation);
+        // a check costs 5 squarings, every 500 squarings it is 1% of the time
+        assert_eq!(recommendation.check_interval, 500);
+        // 10ms of squaring
+        assert_eq!(recommendation.step, 10_000);
+        // 1.01us per iteration
+        assert_eq!(recommendation.expected_iterations, 9_900_990);
+
+        // twice the interval, twice the iterations under a harder target
+        let slower = calibration.recommend(Duration::from_secs(20));
+        assert_consistent(&slower);
+        assert_eq!(slower.expected_iterations, 19_801_980);
+        assert!(slower.target < recommendation.target);
+    }
+
+    #[test]
+    fn recommends_at_least_one_check_per_step() {
+        // checks cost nothing next to squarings, and a step is under one squaring
+        let calibration = Calibration {
+            squarings_per_second: 10.0,
+            checks_per_second: 1e12,
+        };
+        let recommendation = calibration.recommend(Duration::from_secs(10));
+        assert_consistent(&recommendation);
+        assert_eq!(recommendation.check_interval, 1);
+        assert_eq!(recommendation.step, 1);
+        assert_eq!(recommendation.expected_iterations, 99);
+
+        // an interval too short for a single check, every state meets the target
+        let recommendation = calibration.recommend(Duration::from_millis(1));
+        assert_consistent(&recommendation);
+        assert_eq!(recommendation.expected_iterations, 1);
+        assert_eq!(recommendation.target, (Integer::from(1) << 256u32) - 1);
+    }
+}
@@ -0,0 +1,585 @@
+/// Chain work, fork choice and verification for chains of SeqPoW blocks.
+use super::bigint::{Integer, Order};
+use super::context::VerifierContext;
+use super::difficulty;
+use super::error::ChainError;
+use super::limits::VerifyLimits;
+use super::scheme::Scheme;
+use ecvrf;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Add, AddAssign};
+
+/// Work of a block or a chain of blocks: the summed difficulties of their targets, then, as a
+/// tie-break, the summed iterations they saved against the largest possible count.
+///
+/// Works compare by difficulty first and by the tie-break only on equal difficulty, so no
+/// number of blocks solved in few iterations outweighs a single difficulty step.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Work {
+    /// Sum of `difficulty::difficulty` of the targets.
+    pub difficulty: Integer,
+    /// Sum of 2^64 - 1 - iters.
+    pub tie_break: Integer,
+}
+
+impl Add for Work {
+    type Output = Work;
+
+    fn add(mut self, other: Work) -> Work {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Work {
+    fn add_assign(&mut self, other: Work) {
+        self.difficulty += other.difficulty;
+        self.tie_break += other.tie_break;
+    }
+}
+
+/// Work of a block solved under `target` after `iters` iterations.
+///
+/// The target dominates, and of two blocks under the same target the one with fewer
+/// iterations, which its miner could publish sooner, is heavier. Any target below 2^256 has a
+/// difficulty of at least 1, so a child outweighs its parent.
+pub fn block_work(target: &Integer, iters: u64) -> Work {
+    Work {
+        difficulty: difficulty::difficulty(target),
+        tie_break: Integer::from(u64::MAX - iters),
+    }
+}
+
+/// Total `block_work` of a chain of `(target, iters)` blocks.
+pub fn cumulative_work<'a, I>(blocks: I) -> Work
+where
+    I: IntoIterator<Item = (&'a Integer, u64)>,
+{
+    blocks
+        .into_iter()
+        .fold(Work::default(), |work, (target, iters)| {
+            work + block_work(target, iters)
+        })
+}
+
+/// Fork choice between two tips: the one with more cumulative work wins, equal work goes to the
+/// lower hash. `Greater` means `a` is preferred.
+pub fn compare_tips<H: Ord>(work_a: &Work, hash_a: &H, work_b: &Work, hash_b: &H) -> Ordering {
+    work_a.cmp(work_b).then_with(|| hash_b.cmp(hash_a))
+}
+
+struct Node<H> {
+    parent: Option<H>,
+    height: u64,
+    work: Work,
+}
+
+/// A tree of blocks identified by their hashes `H`, tracking cumulative work and the best tip.
+pub struct BlockTree<H> {
+    nodes: HashMap<H, Node<H>>,
+    best: Option<H>,
+}
+
+impl<H: Clone + Eq + Hash + Ord> Default for BlockTree<H> {
+    fn default() -> Self {
+        BlockTree {
+            nodes: HashMap::new(),
+            best: None,
+        }
+    }
+}
+
+impl<H: Clone + Eq + Hash + Ord> BlockTree<H> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a block on top of `parent`, or a genesis block for `None`, and returns its
+    /// cumulative work.
+    pub fn insert(
+        &mut self,
+        hash: H,
+        parent: Option<H>,
+        target: &Integer,
+        iters: u64,
+    ) -> Result<&Work, ChainError> {
+        if self.nodes.contains_key(&hash) {
+            return Err(ChainError::DuplicateBlock);
+        }
+        let (height, parent_work) = match &parent {
+            None => (0, Work::default()),
+            Some(p) => {
+                let node = self.nodes.get(p).ok_or(ChainError::UnknownParent)?;
+                (node.height + 1, node.work.clone())
+            }
+        };
+        let work = parent_work + block_work(target, iters);
+
+        let is_best = match &self.best {
+            None => true,
+            Some(best) => {
+                compare_tips(&work, &hash, &self.nodes[best].work, best) == Ordering::Greater
+            }
+        };
+        if is_best {
+            self.best = Some(hash.clone());
+        }
+
+        let node = self.nodes.entry(hash).or_insert(Node {
+            parent,
+            height,
+            work,
+        });
+        Ok(&node.work)
+    }
+
+    pub fn contains(&self, hash: &H) -> bool {
+        self.nodes.contains_key(hash)
+    }
+
+    pub fn parent(&self, hash: &H) -> Option<&H> {
+        self.nodes.get(hash).and_then(|node| node.parent.as_ref())
+    }
+
+    /// Number of blocks below `hash`, 0 for a genesis block.
+    pub fn height(&self, hash: &H) -> Option<u64> {
+        self.nodes.get(hash).map(|node| node.height)
+    }
+
+    /// Cumulative work of the chain ending at `hash`.
+    pub fn work(&self, hash: &H) -> Option<&Work> {
+        self.nodes.get(hash).map(|node| &node.work)
+    }
+
+    /// The tip chosen by `compare_tips` among all blocks. Children always outweigh their parents,
+    /// so it is a leaf.
+    pub fn best_tip(&self) -> Option<&H> {
+        self.best.as_ref()
+    }
+
+    /// Hashes from `hash` down to its genesis block.
+    pub fn ancestors<'a>(&'a self, hash: &'a H) -> impl Iterator<Item = &'a H> + 'a {
+        let start = self.nodes.get_key_value(hash).map(|(h, _)| h);
+        std::iter::successors(start, move |h| self.parent(h))
+    }
+}
+
+/// A block as published: the miner's key, the seed it mined from, its target and the solution
+/// with the scheme's proof.
+pub struct Block<P> {
+    pub pubkey: ecvrf::VrfPk,
+    pub seed: Integer,
+    pub target: Integer,
+    pub iters: u64,
+    pub state: Integer,
+    pub proof: P,
+}
+
+/// int(H("seed"||state)), the seed of the block after the one solved at `state`.
+pub fn next_seed(state: &Integer) -> Integer {
+    let mut hasher = Sha256::new();
+    hasher.update("seed".as_bytes());
+    hasher.update(state.to_digits::<u8>(Order::Lsf));
+    Integer::from_digits(&hasher.finalize(), Order::Lsf)
+}
+
+/// The rule setting each block's target from the blocks before it.
+pub trait Retarget {
+    /// Target of the next block, given the `(target, iters)` of every block so far, oldest
+    /// first. The next block's height is `history.len()`.
+    fn target(&self, history: &[(Integer, u64)]) -> Integer;
+}
+
+/// The same target for every block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedTarget(pub Integer);
+
+impl Retarget for FixedTarget {
+    fn target(&self, _history: &[(Integer, u64)]) -> Integer {
+        self.0.clone()
+    }
+}
+
+impl<F: Fn(&[(Integer, u64)]) -> Integer> Retarget for F {
+    fn target(&self, history: &[(Integer, u64)]) -> Integer {
+        self(history)
+    }
+}
+
+/// Verifies blocks mined with `scheme` on top of `genesis_seed`: each seed must be the
+/// `next_seed` of the solution before it, each target the one `retarget` gives, and each
+/// solution must verify under `limits`, which apply to every block on its own. Returns the
+/// cumulative work of the chain, or the error of the lowest invalid block.
+pub fn verify_chain<'a, S, R, I>(
+    scheme: &S,
+    modulus: &Integer,
+    genesis_seed: &Integer,
+    check_interval: u64,
+    retarget: &R,
+    blocks: I,
+    limits: &VerifyLimits,
+) -> Result<Work, ChainError>
+where
+    S: Scheme,
+    S::Proof: 'a,
+    R: Retarget + ?Sized,
+    I: IntoIterator<Item = &'a Block<S::Proof>>,
+{
+    let mut seed = genesis_seed.clone();
+    let mut history = Vec::new();
+    let mut work = Work::default();
+    for (height, block) in (0u64..).zip(blocks) {
+        if block.seed != seed {
+            return Err(ChainError::SeedMismatch { height });
+        }
+        if block.target != retarget.target(&history) {
+            return Err(ChainError::TargetMismatch { height });
+        }
+
+        let ctx = VerifierContext::new(modulus, &block.pubkey, &block.target);
+        scheme
+            .verify(
+                &ctx,
+                &block.seed,
+                block.iters,
+                check_interval,
+                &block.state,
+                &block.proof,
+                limits,
+            )
+            .map_err(|error| ChainError::InvalidSolution { height, error })?;
+
+        work += block_work(&block.target, block.iters);
+        seed = next_seed(&block.state);
+        history.push((block.target.clone(), block.iters));
+    }
+    Ok(work)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::error::{Limit, VerifyError};
+    use super::super::scheme::Wes19;
+    use super::super::{testing, util, wes19};
+    use super::*;
+
+    /// A target of difficulty about 2^bits.
+    fn target(bits: u32) -> Integer {
+        Integer::from(1) << (256 - bits)
+    }
+
+    /// A target of difficulty exactly `d`.
+    fn target_of_difficulty(d: u32) -> Integer {
+        let target = (Integer::from(1) << 256u32) / d - 1u32;
+        assert_eq!(difficulty::difficulty(&target), d);
+        target
+    }
+
+    #[test]
+    fn block_work_orders_by_target_then_iterations() {
+        let (easy, hard) = (target(8), target(9));
+        assert!(block_work(&hard, u64::MAX) > block_work(&easy, 0));
+        assert!(block_work(&easy, 1) > block_work(&easy, 2));
+        assert!(block_work(&easy, u64::MAX) > Work::default());
+        assert_eq!(
+            block_work(&easy, 10),
+            Work {
+                difficulty: difficulty::difficulty(&easy),
+                tie_break: Integer::from(u64::MAX - 10),
+            }
+        );
+    }
+
+    #[test]
+    fn tie_breaks_never_add_up_to_a_difficulty_step() {
+        let (d255, d256) = (target_of_difficulty(255), target_of_difficulty(256));
+        // two blocks solved at once against two that took every iteration and one more
+        // difficulty step between them
+        let fast = cumulative_work(vec![(&d255, 0), (&d255, 0)]);
+        let slow = cumulative_work(vec![(&d255, u64::MAX), (&d256, u64::MAX)]);
+        assert!(slow > fast);
+        assert_eq!(slow.difficulty, 511);
+        assert_eq!(fast.tie_break, Integer::from(u64::MAX) * 2u32);
+
+        // the same over forks of 100 blocks
+        let mut tree = BlockTree::new();
+        tree.insert(0u32, None, &d255, 0).unwrap();
+        for hash in 1..=100 {
+            tree.insert(hash, Some(hash - 1), &d255, 0).unwrap();
+        }
+        assert_eq!(tree.best_tip(), Some(&100));
+        let mut parent = 0;
+        for hash in 1001..=1099 {
+            tree.insert(hash, Some(parent), &d255, u64::MAX).unwrap();
+            parent = hash;
+        }
+        tree.insert(1100, Some(parent), &d256, u64::MAX).unwrap();
+        assert_eq!(tree.best_tip(), Some(&1100));
+        assert!(tree.work(&1100).unwrap().tie_break < tree.work(&100).unwrap().tie_break);
+    }
+
+    #[test]
+    fn insert_tracks_height_work_and_ancestors() {
+        let t = target(8);
+        let mut tree = BlockTree::new();
+        assert_eq!(tree.best_tip(), None);
+        assert_eq!(
+            tree.insert(0u32, None, &t, 10).unwrap(),
+            &block_work(&t, 10)
+        );
+        tree.insert(1, Some(0), &t, 20).unwrap();
+        tree.insert(2, Some(1), &t, 30).unwrap();
+
+        assert!(tree.contains(&2) && !tree.contains(&3));
+        assert_eq!(tree.height(&0), Some(0));
+        assert_eq!(tree.height(&2), Some(2));
+        assert_eq!(tree.parent(&2), Some(&1));
+        assert_eq!(tree.parent(&0), None);
+        let blocks = [(&t, 10), (&t, 20), (&t, 30)];
+        assert_eq!(
+            tree.work(&2),
+            Some(&cumulative_work(blocks.iter().cloned()))
+        );
+        assert_eq!(tree.ancestors(&2).cloned().collect::<Vec<_>>(), [2, 1, 0]);
+        assert_eq!(tree.best_tip(), Some(&2));
+    }
+
+    #[test]
+    fn best_tip_follows_the_heavier_fork() {
+        let t = target(8);
+        let mut tree = BlockTree::new();
+        tree.insert("genesis", None, &t, 10).unwrap();
+        tree.insert("a1", Some("genesis"), &t, 10).unwrap();
+        tree.insert("a2", Some("a1"), &t, 10).unwrap();
+        // a shorter fork under a 4 times harder target outweighs the longer one
+        tree.insert("b1", Some("genesis"), &target(10), 10).unwrap();
+        assert_eq!(tree.best_tip(), Some(&"b1"));
+        // blocks on the lighter fork do not move the tip until it is heavier
+        tree.insert("a3", Some("a2"), &t, 10).unwrap();
+        assert_eq!(tree.best_tip(), Some(&"b1"));
+        tree.insert("a4", Some("a3"), &t, 10).unwrap();
+        tree.insert("a5", Some("a4"), &t, 10).unwrap();
+        assert_eq!(tree.best_tip(), Some(&"a5"));
+        assert!(tree.work(&"a5") > tree.work(&"b1"));
+    }
+
+    #[test]
+    fn best_tip_breaks_ties_by_lower_hash() {
+        let t = target(8);
+        for order in [[2u32, 3], [3, 2]].iter() {
+            let mut tree = BlockTree::new();
+            tree.insert(1, None, &t, 10).unwrap();
+            for &hash in order {
+                tree.insert(hash, Some(1), &t, 10).unwrap();
+            }
+            assert_eq!(tree.work(&2), tree.work(&3));
+            assert_eq!(tree.best_tip(), Some(&2));
+        }
+        // fewer iterations under the same target win over the lower hash
+        let mut tree = BlockTree::new();
+        tree.insert(1, None, &t, 10).unwrap();
+        tree.insert(2, Some(1), &t, 11).unwrap();
+        tree.insert(3, Some(1), &t, 10).unwrap();
+        assert_eq!(tree.best_tip(), Some(&3));
+    }
+
+    #[test]
+    fn insert_rejects_unknown_parents_and_duplicates() {
+        let t = target(8);
+        let mut tree = BlockTree::new();
+        tree.insert(1u32, None, &t, 10).unwrap();
+        assert_eq!(
+            tree.insert(2, Some(7), &t, 10),
+            Err(ChainError::UnknownParent)
+        );
+        assert!(!tree.contains(&2));
+
+        let work = tree.work(&1).unwrap().clone();
+        assert_eq!(tree.insert(1, None, &t, 1), Err(ChainError::DuplicateBlock));
+        assert_eq!(tree.work(&1), Some(&work));
+        assert_eq!(tree.best_tip(), Some(&1));
+    }
+
+    const CHECK_INTERVAL: u64 = 4;
+
+    /// Halves the target with every block, from one that every state meets.
+    fn halving(history: &[(Integer, u64)]) -> Integer {
+        testing::any_target() >> history.len() as u32
+    }
+
+    /// A chain of `len` wes19 blocks on top of `genesis_seed`, each by its own miner, linked and
+    /// retargeted as `verify_chain` expects with `halving`.
+    fn mine_chain(modulus: &Integer, genesis_seed: &Integer, len: usize) -> Vec<Block<Integer>> {
+        let mut blocks: Vec<Block<Integer>> = Vec::new();
+        let mut history = Vec::new();
+        let mut seed = genesis_seed.clone();
+        for _ in 0..len {
+            let pubkey = testing::pubkey();
+            let target = halving(&history);
+            let g = util::h_g(modulus, &pubkey, &seed);
+            let (state, proof, iters) = wes19::mine(
+                CHECK_INTERVAL,
+                CHECK_INTERVAL,
+                &pubkey,
+                modulus,
+                &g,
+                &target,
+            );
+            history.push((target.clone(), iters));
+            let next = next_seed(&state);
+            blocks.push(Block {
+                pubkey,
+                seed,
+                target,
+                iters,
+                state,
+                proof,
+            });
+            seed = next;
+        }
+        blocks
+    }
+
+    fn verify_with<R: Retarget>(
+        modulus: &Integer,
+        genesis_seed: &Integer,
+        retarget: &R,
+        blocks: &[Block<Integer>],
+        limits: &VerifyLimits,
+    ) -> Result<Work, ChainError> {
+        verify_chain(
+            &Wes19,
+            modulus,
+            genesis_seed,
+            CHECK_INTERVAL,
+            retarget,
+            blocks,
+            limits,
+        )
+    }
+
+    fn verify(
+        modulus: &Integer,
+        genesis_seed: &Integer,
+        blocks: &[Block<Integer>],
+    ) -> Result<Work, ChainError> {
+        let limits = VerifyLimits::default();
+        verify_with(modulus, genesis_seed, &halving, blocks, &limits)
+    }
+
+    #[test]
+    fn verifies_linked_chain() {
+        let modulus = testing::modulus();
+        let genesis = Integer::from(7);
+        let blocks = mine_chain(&modulus, &genesis, 5);
+        let work = cumulative_work(blocks.iter().map(|block| (&block.target, block.iters)));
+        assert_eq!(verify(&modulus, &genesis, &blocks), Ok(work));
+        assert_eq!(verify(&modulus, &genesis, &[]), Ok(Work::default()));
+    }
+
+    #[test]
+    fn rejects_broken_seed_linkage() {
+        let modulus = testing::modulus();
+        let genesis = Integer::from(7);
+        let blocks = mine_chain(&modulus, &genesis, 5);
+        assert_eq!(
+            verify(&modulus, &Integer::from(8), &blocks),
+            Err(ChainError::SeedMismatch { height: 0 })
+        );
+
+        // a seed from the solution two blocks down, and one changed after mining
+        let mut blocks = mine_chain(&modulus, &genesis, 5);
+        blocks[2].seed = next_seed(&blocks[0].state);
+        assert_eq!(
+            verify(&modulus, &genesis, &blocks),
+            Err(ChainError::SeedMismatch { height: 2 })
+        );
+        let mut blocks = mine_chain(&modulus, &genesis, 5);
+        blocks[3].seed += 1;
+        assert_eq!(
+            verify(&modulus, &genesis, &blocks),
+            Err(ChainError::SeedMismatch { height: 3 })
+        );
+
+        // a changed state changes the next seed
+        let mut blocks = mine_chain(&modulus, &genesis, 5);
+        blocks[4].seed = next_seed(&Integer::from(&blocks[3].state + 1));
+        assert_eq!(
+            verify(&modulus, &genesis, &blocks),
+            Err(ChainError::SeedMismatch { height: 4 })
+        );
+    }
+
+    #[test]
+    fn rejects_targets_off_the_retarget_rule() {
+        let modulus = testing::modulus();
+        let genesis = Integer::from(7);
+        let limits = VerifyLimits::default();
+
+        // a fixed target matches the first block only
+        let blocks = mine_chain(&modulus, &genesis, 3);
+        let fixed = FixedTarget(testing::any_target());
+        assert_eq!(
+            verify_with(&modulus, &genesis, &fixed, &blocks, &limits),
+            Err(ChainError::TargetMismatch { height: 1 })
+        );
+
+        // the closure sees the history of the blocks before
+        let lengths = |history: &[(Integer, u64)]| {
+            assert!(history.iter().all(|(_, iters)| *iters > 0));
+            halving(history)
+        };
+        let mut blocks = mine_chain(&modulus, &genesis, 5);
+        assert!(verify_with(&modulus, &genesis, &lengths, &blocks, &limits).is_ok());
+        blocks[2].target = halving(&[]);
+        assert_eq!(
+            verify_with(&modulus, &genesis, &lengths, &blocks, &limits),
+            Err(ChainError::TargetMismatch { height: 2 })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_solutions_at_their_height() {
+        let modulus = testing::modulus();
+        let genesis = Integer::from(7);
+
+        let mut blocks = mine_chain(&modulus, &genesis, 5);
+        blocks[2].proof = testing::qr_plus_element(&modulus);
+        let error = verify(&modulus, &genesis, &blocks).unwrap_err();
+        assert_eq!(
+            error,
+            ChainError::InvalidSolution {
+                height: 2,
+                error: VerifyError::InvalidProof
+            }
+        );
+        assert_eq!(error.height(), Some(2));
+
+        // the lowest invalid block is reported
+        blocks[1].iters += 1;
+        assert_eq!(
+            verify(&modulus, &genesis, &blocks),
+            Err(ChainError::InvalidSolution {
+                height: 1,
+                error: VerifyError::UncheckedIteration
+            })
+        );
+
+        // limits apply to every block
+        let blocks = mine_chain(&modulus, &genesis, 2);
+        let limits = VerifyLimits {
+            max_iterations: Some(0),
+            ..VerifyLimits::default()
+        };
+        assert_eq!(
+            verify_with(&modulus, &genesis, &halving, &blocks, &limits),
+            Err(ChainError::InvalidSolution {
+                height: 0,
+                error: VerifyError::LimitExceeded(Limit::Iterations)
+            })
+        );
+    }
+}
@@ -0,0 +1,162 @@
+/// Evenly spaced intermediate states recorded while solving, for proving without re-squaring.
+use super::bigint::Integer;
+use super::squaring::Squarer;
+
+/// The states `x^(2^(j * interval))` for `j = 0, 1, ...` of a repeated squaring chain from `x`.
+#[derive(Clone, Debug)]
+pub struct Checkpoints {
+    interval: u64,
+    iterations: u64,
+    max_states: usize,
+    states: Vec<Integer>,
+}
+
+impl Checkpoints {
+    pub fn new(modulus: &Integer, ini_state: &Integer, interval: u64) -> Self {
+        assert!(interval > 0, "checkpoint interval must be positive");
+        Checkpoints {
+            interval,
+            iterations: 0,
+            max_states: usize::MAX,
+            states: vec![ini_state.clone().div_rem_floor(modulus.clone()).1],
+        }
+    }
+
+    /// Checkpoints holding at most `max_states` states for a chain of unknown length: whenever
+    /// the budget is exceeded every other state is dropped and the interval doubles, so a chain
+    /// of T squarings ends up with states about 2T / max_states apart.
+    pub fn with_budget(modulus: &Integer, ini_state: &Integer, max_states: usize) -> Self {
+        assert!(
+            max_states >= 2,
+            "checkpoint budget must hold at least 2 states"
+        );
+        Checkpoints {
+            max_states,
+            ..Checkpoints::new(modulus, ini_state, 1)
+        }
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Number of squarings recorded so far.
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    pub fn states(&self) -> &[Integer] {
+        &self.states
+    }
+
+    /// x^(2^iteration), if `iteration` falls on a checkpoint.
+    pub fn get(&self, iteration: u64) -> Option<&Integer> {
+        if !iteration.is_multiple_of(self.interval) {
+            return None;
+        }
+        self.states.get((iteration / self.interval) as usize)
+    }
+
+    /// Squares `state`, which must be the last state of the chain, `steps` times and records
+    /// every `interval`-th result.
+    pub fn advance(&mut self, squarer: &mut Squarer, state: &mut Integer, steps: u64) {
+        for _ in 0..steps {
+            squarer.square(state);
+            self.iterations += 1;
+            if self.iterations.is_multiple_of(self.interval) {
+                self.states.push(state.clone());
+                if self.states.len() > self.max_states {
+                    self.thin();
+                }
+            }
+        }
+    }
+
+    /// Squarings needed to reach x^(2^iteration) from the closest stored state below it.
+    pub fn distance(&self, iteration: u64) -> u64 {
+        let j = (iteration / self.interval).min(self.states.len() as u64 - 1);
+        iteration - j * self.interval
+    }
+
+    /// x^(2^iteration), squared up from the closest stored state below it.
+    pub fn state_at(&self, squarer: &mut Squarer, iteration: u64) -> Integer {
+        let distance = self.distance(iteration);
+        let mut state = self.states[((iteration - distance) / self.interval) as usize].clone();
+        squarer.square_n(&mut state, distance);
+        state
+    }
+
+    fn thin(&mut self) {
+        let states = std::mem::take(&mut self.states);
+        self.states = states.into_iter().step_by(2).collect();
+        self.interval *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing;
+    use super::*;
+
+    /// The states of a chain from 3, squared directly.
+    fn chain(modulus: &Integer, len: u64) -> Vec<Integer> {
+        let mut squarer = Squarer::new(modulus);
+        let mut states = vec![Integer::from(3)];
+        for i in 0..len as usize {
+            let mut next = states[i].clone();
+            squarer.square(&mut next);
+            states.push(next);
+        }
+        states
+    }
+
+    #[test]
+    fn records_every_interval() {
+        let modulus = testing::modulus();
+        let expected = chain(&modulus, 100);
+        let mut squarer = Squarer::new(&modulus);
+        let mut checkpoints = Checkpoints::new(&modulus, &expected[0], 7);
+        let mut state = expected[0].clone();
+        // in uneven pieces, like the check intervals of a solve loop
+        for &steps in &[5, 1, 30, 64] {
+            checkpoints.advance(&mut squarer, &mut state, steps);
+        }
+        assert_eq!(state, expected[100]);
+        assert_eq!(checkpoints.iterations(), 100);
+        assert_eq!(checkpoints.states().len(), 15);
+        for i in 0..=100 {
+            let stored = checkpoints.get(i);
+            assert_eq!(stored.is_some(), i % 7 == 0, "iteration {}", i);
+            if let Some(stored) = stored {
+                assert_eq!(*stored, expected[i as usize]);
+            }
+            assert_eq!(checkpoints.distance(i), i % 7);
+            assert_eq!(checkpoints.state_at(&mut squarer, i), expected[i as usize]);
+        }
+    }
+
+    #[test]
+    fn budget_thins_and_doubles_the_interval() {
+        let modulus = testing::modulus();
+        let expected = chain(&modulus, 1000);
+        let mut squarer = Squarer::new(&modulus);
+        let mut checkpoints = Checkpoints::with_budget(&modulus, &expected[0], 8);
+        let mut state = expected[0].clone();
+        for done in 1..=1000u64 {
+            checkpoints.advance(&mut squarer, &mut state, 1);
+            assert!(checkpoints.states().len() <= 8);
+            // every state the chain reached so far is still covered
+            assert_eq!(
+                checkpoints.states().len() as u64,
+                done / checkpoints.interval() + 1
+            );
+        }
+        assert_eq!(checkpoints.interval(), 128);
+        for (j, stored) in checkpoints.states().iter().enumerate() {
+            assert_eq!(*stored, expected[j * 128]);
+        }
+        for &i in &[0, 1, 127, 128, 500, 999, 1000] {
+            assert_eq!(checkpoints.state_at(&mut squarer, i), expected[i as usize]);
+        }
+    }
+}
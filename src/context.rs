@@ -0,0 +1,151 @@
+/// Precomputed verifier state for validating many solutions under one parameter set.
+use super::bigint::Integer;
+use super::squaring::Squarer;
+use super::util;
+use ecvrf;
+use sha2::Sha256;
+
+/// Holds the modulus with its reduction constants, the hashed pubkey prefix and the target,
+/// so that `verify_with_context` in each scheme does not recompute them for every block.
+///
+/// Building one takes a few microseconds for a 2048-bit modulus, next to the `hash_to_prime`
+/// of every wes19 or pie19 verification that takes hundreds of milliseconds, so sharing it
+/// saves little there; `bench-context` compares both over a run of blocks.
+#[derive(Clone)]
+pub struct VerifierContext {
+    squarer: Squarer,
+    prefix: Sha256,
+    target: Integer,
+}
+
+impl VerifierContext {
+    pub fn new(modulus: &Integer, pubkey: &ecvrf::VrfPk, target: &Integer) -> Self {
+        VerifierContext {
+            squarer: Squarer::new(modulus),
+            prefix: util::pubkey_prefix(pubkey),
+            target: target.clone(),
+        }
+    }
+
+    pub fn modulus(&self) -> &Integer {
+        self.squarer.modulus()
+    }
+
+    pub fn target(&self) -> &Integer {
+        &self.target
+    }
+
+    /// A fresh squaring engine sharing the precomputed Barrett constants.
+    pub fn squarer(&self) -> Squarer {
+        self.squarer.clone()
+    }
+
+    pub fn h_g(&self, seed: &Integer) -> Integer {
+        util::h_g_with_prefix(self.modulus(), &self.prefix, seed)
+    }
+
+    pub fn h_state(&self, state: &Integer) -> Integer {
+        util::h_state_with_prefix(self.modulus(), &self.prefix, state)
+    }
+
+    /// Whether `state` meets the target.
+    pub fn validate_difficulty(&self, state: &Integer) -> bool {
+        util::validate_difficulty(&self.h_state(state), &self.target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::error::VerifyError;
+    use super::super::limits::VerifyLimits;
+    use super::super::scheme::Scheme;
+    use super::super::testing::{self, Fixture};
+    use super::super::{pie19, sloth, wes19};
+    use super::*;
+
+    /// Checks that the free `verify` of a scheme, which builds its own context, and
+    /// `Scheme::verify` over a context built once agree on the honest solution, a wrong state,
+    /// no state and the `tampered` proof, under the fixture's target and one nothing meets.
+    fn assert_agree<S, F>(f: &Fixture<S>, tampered: S::Proof, free: F)
+    where
+        S: Scheme,
+        S::Proof: Clone,
+        F: Fn(&Integer, &Integer, &S::Proof) -> Result<(), VerifyError>,
+    {
+        let cases = [
+            (f.y.clone(), f.proof.clone()),
+            (testing::qr_plus_element(&f.modulus), f.proof.clone()),
+            (Integer::new(), f.proof.clone()),
+            (f.y.clone(), tampered),
+        ];
+        for target in &[f.target.clone(), Integer::new()] {
+            let ctx = VerifierContext::new(&f.modulus, &f.pubkey, target);
+            for (y, proof) in &cases {
+                let expected = free(target, y, proof);
+                let with_context = f.scheme.verify(
+                    &ctx,
+                    &f.seed,
+                    f.iters,
+                    f.check_interval,
+                    y,
+                    proof,
+                    &VerifyLimits::default(),
+                );
+                assert_eq!(with_context, expected, "y = {}", y);
+            }
+        }
+        assert_eq!(free(&f.target, &f.y, &f.proof), Ok(()));
+    }
+
+    #[test]
+    fn free_verify_matches_verify_with_context() {
+        let limits = VerifyLimits::default();
+
+        let f = Fixture::wes19();
+        let tampered = testing::qr_plus_element(&f.modulus);
+        assert_agree(&f, tampered, |target, y, pi| {
+            wes19::verify(
+                &f.modulus,
+                &f.g,
+                f.iters,
+                f.check_interval,
+                y,
+                pi,
+                &f.pubkey,
+                target,
+                &limits,
+            )
+        });
+
+        let f = Fixture::pie19();
+        let mut tampered = f.proof.clone();
+        tampered.pi_list[0] = testing::qr_plus_element(&f.modulus);
+        assert_agree(&f, tampered, |target, y, proof| {
+            pie19::verify(
+                &f.modulus,
+                &f.g,
+                y,
+                f.iters,
+                f.check_interval,
+                proof,
+                &f.pubkey,
+                target,
+                &limits,
+            )
+        });
+
+        let f = Fixture::sloth();
+        assert_agree(&f, (), |target, witness, _| {
+            sloth::verify(
+                &f.modulus,
+                &f.seed,
+                f.iters,
+                f.check_interval,
+                witness,
+                &f.pubkey,
+                target,
+                &limits,
+            )
+        });
+    }
+}
@@ -0,0 +1,186 @@
+/// What a target means in expected work.
+///
+/// A checked state meets `target` when the 256-bit hash in `util::validate_difficulty` is at most
+/// `target`, which for a random state happens with probability p = (target + 1) / 2^256. The
+/// number of checks until a solution is geometric with mean 1 / p, and mining does one check
+/// every `check_interval` iterations.
+use super::bigint::Integer;
+use super::util;
+use ecvrf;
+
+/// Probability p that one difficulty check succeeds.
+pub fn success_probability(target: &Integer) -> f64 {
+    if *target < 0 {
+        return 0.0;
+    }
+    ((Integer::from(target + 1u32)).to_f64() / 2f64.powi(256)).min(1.0)
+}
+
+/// Expected iterations until a state meets `target`.
+pub fn expected_iterations(target: &Integer, check_interval: u64) -> f64 {
+    check_interval as f64 / success_probability(target)
+}
+
+/// Iterations within which a solution is found with probability `q`, in [0, 1).
+pub fn quantile_iterations(target: &Integer, check_interval: u64, q: f64) -> f64 {
+    let p = success_probability(target);
+    if p >= 1.0 {
+        return check_interval as f64;
+    }
+    // smallest k with 1 - (1 - p)^k >= q
+    let checks = ((-q).ln_1p() / (-p).ln_1p()).ceil().max(1.0);
+    checks * check_interval as f64
+}
+
+/// Expected number of checks to meet `target`, floor(2^256 / (target + 1)), as an exact integer
+/// that sums without rounding. A negative target counts as 0.
+pub fn difficulty(target: &Integer) -> Integer {
+    let target = if *target < 0 {
+        Integer::new()
+    } else {
+        target.clone()
+    };
+    (Integer::from(1) << 256u32) / (target + 1u32)
+}
+
+/// Outcome of running the real difficulty check on random states.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MonteCarlo {
+    pub samples: u64,
+    pub successes: u64,
+    /// `success_probability` of the target.
+    pub expected_probability: f64,
+}
+
+impl MonteCarlo {
+    pub fn observed_probability(&self) -> f64 {
+        self.successes as f64 / self.samples as f64
+    }
+
+    /// Distance between the observed and expected success counts in standard deviations of the
+    /// binomial distribution.
+    pub fn z_score(&self) -> f64 {
+        let n = self.samples as f64;
+        let p = self.expected_probability;
+        let sd = (n * p * (1.0 - p)).sqrt();
+        if sd == 0.0 {
+            return if self.successes as f64 == n * p {
+                0.0
+            } else {
+                f64::INFINITY
+            };
+        }
+        (self.successes as f64 - n * p) / sd
+    }
+
+    /// Whether the observation is within `max_z` standard deviations of the analytic numbers.
+    pub fn agrees(&self, max_z: f64) -> bool {
+        self.z_score().abs() <= max_z
+    }
+}
+
+/// Checks `samples` random states modulo `modulus` against `target` with `h_state` and
+/// `validate_difficulty`, as `solve` does. Deterministic for a given `seed`.
+pub fn monte_carlo(
+    modulus: &Integer,
+    pubkey: &ecvrf::VrfPk,
+    target: &Integer,
+    samples: u64,
+    seed: u64,
+) -> MonteCarlo {
+    let mut rng = util::SplitMix64::new(seed);
+    let prefix = util::pubkey_prefix(pubkey);
+    let successes = (0..samples)
+        .filter(|_| {
+            let state = rng.next_integer(modulus);
+            let hstate = util::h_state_with_prefix(modulus, &prefix, &state);
+            util::validate_difficulty(&hstate, target)
+        })
+        .count() as u64;
+
+    MonteCarlo {
+        samples,
+        successes,
+        expected_probability: success_probability(target),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing;
+    use super::*;
+
+    fn two_pow_256() -> Integer {
+        Integer::from(1) << 256u32
+    }
+
+    #[test]
+    fn monte_carlo_agrees_with_success_probability() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        for &bits in &[1u32, 2, 4, 6] {
+            let target = (two_pow_256() >> bits) - 1u32;
+            let run = monte_carlo(&modulus, &pubkey, &target, 2000, u64::from(bits));
+            assert_eq!(run.expected_probability, 1.0 / f64::from(1u32 << bits));
+            assert!(run.agrees(5.0), "target 2^(256 - {}): {:?}", bits, run);
+        }
+
+        // the edges, where the binomial distribution has (almost) no spread
+        let never = monte_carlo(&modulus, &pubkey, &Integer::new(), 500, 1);
+        assert_eq!(never.successes, 0);
+        assert!(never.agrees(1e-30));
+        let always = monte_carlo(&modulus, &pubkey, &(two_pow_256() - 1u32), 500, 1);
+        assert_eq!((always.successes, always.z_score()), (500, 0.0));
+
+        // and a disagreement
+        let wrong = MonteCarlo {
+            expected_probability: 0.5,
+            ..always
+        };
+        assert!(!wrong.agrees(5.0));
+    }
+
+    #[test]
+    fn success_probability_at_the_edges() {
+        assert_eq!(success_probability(&Integer::from(-1)), 0.0);
+        assert_eq!(success_probability(&Integer::new()), 2f64.powi(-256));
+        assert_eq!(success_probability(&(two_pow_256() - 1u32)), 1.0);
+        assert_eq!(success_probability(&(two_pow_256() * 2u32)), 1.0);
+    }
+
+    #[test]
+    fn expected_iterations_at_the_edges() {
+        assert_eq!(expected_iterations(&(two_pow_256() - 1u32), 10), 10.0);
+        assert_eq!(
+            expected_iterations(&((two_pow_256() >> 2) - 1u32), 10),
+            40.0
+        );
+        assert_eq!(expected_iterations(&Integer::new(), 1), 2f64.powi(256));
+        assert_eq!(expected_iterations(&Integer::from(-1), 1), f64::INFINITY);
+    }
+
+    #[test]
+    fn quantile_iterations_at_the_edges() {
+        let quarter = (two_pow_256() >> 2) - 1u32;
+        // 1 - (3/4)^k >= 1/2 first holds at k = 3
+        assert_eq!(quantile_iterations(&quarter, 10, 0.5), 30.0);
+        assert_eq!(quantile_iterations(&quarter, 10, 0.0), 10.0);
+        // a certain check needs one, an impossible one never succeeds
+        assert_eq!(quantile_iterations(&(two_pow_256() - 1u32), 10, 0.99), 10.0);
+        assert_eq!(
+            quantile_iterations(&Integer::from(-1), 10, 0.5),
+            f64::INFINITY
+        );
+    }
+
+    #[test]
+    fn difficulty_at_the_edges() {
+        assert_eq!(difficulty(&(two_pow_256() - 1u32)), 1);
+        assert_eq!(difficulty(&((two_pow_256() >> 8) - 1u32)), 256);
+        assert_eq!(difficulty(&Integer::new()), two_pow_256());
+        assert_eq!(difficulty(&Integer::from(-5)), two_pow_256());
+        // rounded down, never 0 for a target up to 2^256 - 1
+        assert_eq!(difficulty(&(two_pow_256() * 2u32)), 0);
+        assert_eq!(difficulty(&Integer::from(2)), two_pow_256() / 3u32);
+    }
+}
@@ -0,0 +1,168 @@
+use std::error::Error;
+use std::fmt;
+
+/// Reasons for rejecting an element that should lie in the signed quadratic residue group QR⁺_N.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupError {
+    /// The element is not in `[1, N)`.
+    OutOfRange,
+    /// The element shares a factor with `N`.
+    NotCoprime,
+    /// The element has order 2 (e.g. `-1`).
+    LowOrder,
+    /// The element is not the canonical representative `min(x, N - x)`.
+    NotCanonical,
+    /// The element has Jacobi symbol -1, so neither it nor `N - x` is a quadratic residue.
+    NonResidue,
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GroupError::OutOfRange => write!(f, "element is not in [1, N)"),
+            GroupError::NotCoprime => write!(f, "element is not coprime to N"),
+            GroupError::LowOrder => write!(f, "element has low order"),
+            GroupError::NotCanonical => write!(f, "element is not a canonical QR+ representative"),
+            GroupError::NonResidue => write!(f, "element has Jacobi symbol -1"),
+        }
+    }
+}
+
+impl Error for GroupError {}
+
+/// The `VerifyLimits` bound a verification hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Iterations,
+    ProofElements,
+    ElementBytes,
+    Depth,
+    VerifierSquarings,
+    Duration,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Iterations => write!(f, "too many iterations"),
+            Limit::ProofElements => write!(f, "too many proof elements"),
+            Limit::ElementBytes => write!(f, "element too large"),
+            Limit::Depth => write!(f, "proof too deep"),
+            Limit::VerifierSquarings => write!(f, "too many squarings for the verifier"),
+            Limit::Duration => write!(f, "time budget exhausted"),
+        }
+    }
+}
+
+/// Reasons for rejecting a solution in `verify`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// An element of the statement or the proof is not in QR⁺_N.
+    Group(GroupError),
+    /// The proof does not have the number of elements `iterations` calls for.
+    ProofLength { expected: usize, actual: usize },
+    /// Verifying would exceed the caller's `VerifyLimits`.
+    LimitExceeded(Limit),
+    /// The iterations are not a positive multiple of the check interval, so `mine` never
+    /// checked the difficulty there.
+    UncheckedIteration,
+    /// The final state does not meet the target.
+    TargetNotMet,
+    /// A state before the final one already met the target, so the solution is not the first.
+    EarlierSolution { iteration: u64 },
+    /// The proof does not show that the final state follows from the seed.
+    InvalidProof,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::Group(e) => write!(f, "{}", e),
+            VerifyError::ProofLength { expected, actual } => {
+                write!(f, "proof has {} elements, expected {}", actual, expected)
+            }
+            VerifyError::LimitExceeded(limit) => write!(f, "verify limit exceeded: {}", limit),
+            VerifyError::UncheckedIteration => {
+                write!(f, "iterations are not a multiple of the check interval")
+            }
+            VerifyError::TargetNotMet => write!(f, "state does not meet the target"),
+            VerifyError::EarlierSolution { iteration } => {
+                write!(f, "state at iteration {} already met the target", iteration)
+            }
+            VerifyError::InvalidProof => write!(f, "invalid proof"),
+        }
+    }
+}
+
+impl Error for VerifyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            VerifyError::Group(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<GroupError> for VerifyError {
+    fn from(e: GroupError) -> Self {
+        VerifyError::Group(e)
+    }
+}
+
+/// Reasons for rejecting a block in a `chain::BlockTree` or `chain::verify_chain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainError {
+    /// The parent block is not in the tree.
+    UnknownParent,
+    /// A block with the same hash is already in the tree.
+    DuplicateBlock,
+    /// The seed of the block at `height` is not derived from the solution before it.
+    SeedMismatch { height: u64 },
+    /// The block at `height` does not use the target the retarget rule gives.
+    TargetMismatch { height: u64 },
+    /// The solution of the block at `height` does not verify.
+    InvalidSolution { height: u64, error: VerifyError },
+}
+
+impl ChainError {
+    /// Height of the first invalid block, for the errors of `chain::verify_chain`.
+    pub fn height(&self) -> Option<u64> {
+        match self {
+            ChainError::UnknownParent | ChainError::DuplicateBlock => None,
+            ChainError::SeedMismatch { height }
+            | ChainError::TargetMismatch { height }
+            | ChainError::InvalidSolution { height, .. } => Some(*height),
+        }
+    }
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChainError::UnknownParent => write!(f, "parent block is unknown"),
+            ChainError::DuplicateBlock => write!(f, "block is already known"),
+            ChainError::SeedMismatch { height } => {
+                write!(
+                    f,
+                    "block {} does not follow from the previous solution",
+                    height
+                )
+            }
+            ChainError::TargetMismatch { height } => {
+                write!(f, "block {} has the wrong target", height)
+            }
+            ChainError::InvalidSolution { height, error } => {
+                write!(f, "block {} is invalid: {}", height, error)
+            }
+        }
+    }
+}
+
+impl Error for ChainError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ChainError::InvalidSolution { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,71 @@
+/// Fixed-base exponentiation with a precomputed window table.
+///
+/// Only `wes19::prove_with_table` uses it, since there `g` is the base of every exponentiation.
+/// `pie19::prove` has no fixed base to precompute: each round raises x_i and mu_i to r_i once
+/// and both change every round, and the midpoint x_i^(2^h) is h squarings, which a table would
+/// have to spend on building its own rows first.
+use super::bigint::Integer;
+use super::squaring::Squarer;
+
+// wider windows do not pay off for the exponent sizes used here
+const MAX_WINDOW: u32 = 16;
+
+/// Powers of a fixed base `g` for exponents of at most `max_bits` bits: row `i` holds
+/// `g^(d * 2^(window * i))` for every `window`-bit digit `d`, so an exponentiation costs one
+/// multiplication per window and no squarings.
+pub struct FixedBaseTable {
+    window: u32,
+    rows: Vec<Vec<Integer>>,
+}
+
+impl FixedBaseTable {
+    /// Builds the widest table for `max_bits`-bit exponents that fits in `max_entries` integers.
+    pub fn new(squarer: &mut Squarer, base: &Integer, max_bits: u32, max_entries: usize) -> Self {
+        let max_bits = max_bits.max(1);
+        let mut window = 1;
+        while window < MAX_WINDOW.min(max_bits)
+            && (max_bits.div_ceil(window + 1) as usize) << (window + 1) <= max_entries
+        {
+            window += 1;
+        }
+
+        let mut row_base = base.clone().div_rem_floor(squarer.modulus().clone()).1;
+        let mut rows = Vec::new();
+        for _ in 0..max_bits.div_ceil(window) {
+            let mut row = Vec::with_capacity(1 << window);
+            row.push(Integer::from(1));
+            for d in 1..(1usize << window) {
+                let mut entry = row[d - 1].clone();
+                squarer.mul(&mut entry, &row_base);
+                row.push(entry);
+            }
+            squarer.square_n(&mut row_base, u64::from(window));
+            rows.push(row);
+        }
+
+        FixedBaseTable { window, rows }
+    }
+
+    /// Width in bits of the digits the table is indexed by.
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    /// g^exp mod N, `exp` must be non-negative and fit in the `max_bits` the table was built for.
+    pub fn pow(&self, squarer: &mut Squarer, exp: &Integer) -> Integer {
+        assert!(
+            exp.significant_bits() as usize <= self.rows.len() * self.window as usize,
+            "exponent too large for fixed-base table"
+        );
+        let mut acc = Integer::from(1);
+        for (i, row) in self.rows.iter().enumerate() {
+            let pos = i as u32 * self.window;
+            let digit =
+                (0..self.window).fold(0, |acc, k| acc | ((exp.get_bit(pos + k) as usize) << k));
+            if digit != 0 {
+                squarer.mul(&mut acc, &row[digit]);
+            }
+        }
+        acc
+    }
+}
@@ -1,4 +1,28 @@
+// `Integer::from(&a op &b)` is needed to complete rug's lazy expressions, but is an identity
+// conversion with the pure-Rust backend
+#![cfg_attr(not(feature = "gmp"), allow(clippy::useless_conversion))]
+
+pub mod bigint;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod calibrate;
+pub mod chain;
+pub mod checkpoint;
+pub mod context;
+pub mod difficulty;
+pub mod error;
+pub mod fixed_base;
+pub mod limits;
+pub mod observer;
 pub mod pie19;
+pub mod report;
+pub mod scheduler;
+pub mod scheme;
 pub mod sloth;
+pub mod squaring;
+pub mod stream;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "trapdoor")]
+pub mod trapdoor;
 pub mod util;
 pub mod wes19;
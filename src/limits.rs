@@ -0,0 +1,213 @@
+/// Resource limits for verifying proofs received from untrusted peers.
+use super::bigint::Integer;
+use super::error::{Limit, VerifyError};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Squarings between two wall-clock checks in the verification loops.
+pub(crate) const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// Bounds on the work a single `verify` call may do, checked before the expensive parts.
+///
+/// The default sets no limit. The wall-clock budget is not enforced on wasm32, which has no
+/// clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VerifyLimits {
+    /// Largest accepted number of iterations.
+    pub max_iterations: Option<u64>,
+    /// Largest accepted number of group elements in a proof.
+    pub max_proof_elements: Option<usize>,
+    /// Largest accepted size in bytes of any integer in the statement or the proof.
+    pub max_element_bytes: Option<usize>,
+    /// Largest accepted pie19 proof depth. The prover picks the depth, and a deep proof with
+    /// few rounds leaves up to 2^depth squarings to the verifier.
+    pub max_depth: Option<u32>,
+    /// Largest accepted number of squarings the verifier does itself: the whole chain for
+    /// sloth, the rounds left below the pie19 depth.
+    pub max_verifier_squarings: Option<u64>,
+    /// Wall-clock budget for the whole verification.
+    pub max_duration: Option<Duration>,
+}
+
+impl VerifyLimits {
+    pub fn check_iterations(&self, iterations: u64) -> Result<(), VerifyError> {
+        match self.max_iterations {
+            Some(max) if iterations > max => Err(VerifyError::LimitExceeded(Limit::Iterations)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_proof_elements(&self, elements: usize) -> Result<(), VerifyError> {
+        match self.max_proof_elements {
+            Some(max) if elements > max => Err(VerifyError::LimitExceeded(Limit::ProofElements)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_depth(&self, depth: u32) -> Result<(), VerifyError> {
+        match self.max_depth {
+            Some(max) if depth > max => Err(VerifyError::LimitExceeded(Limit::Depth)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_verifier_squarings(&self, squarings: u64) -> Result<(), VerifyError> {
+        match self.max_verifier_squarings {
+            Some(max) if squarings > max => {
+                Err(VerifyError::LimitExceeded(Limit::VerifierSquarings))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_element(&self, x: &Integer) -> Result<(), VerifyError> {
+        match self.max_element_bytes {
+            Some(max) if (x.significant_bits() as usize).div_ceil(8) > max => {
+                Err(VerifyError::LimitExceeded(Limit::ElementBytes))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The end of the wall-clock budget of one verification.
+pub(crate) struct Deadline {
+    #[cfg(not(target_arch = "wasm32"))]
+    end: Option<Instant>,
+}
+
+impl Deadline {
+    pub(crate) fn start(limits: &VerifyLimits) -> Self {
+        #[cfg(target_arch = "wasm32")]
+        let _ = limits;
+        Deadline {
+            #[cfg(not(target_arch = "wasm32"))]
+            end: limits
+                .max_duration
+                .and_then(|d| Instant::now().checked_add(d)),
+        }
+    }
+
+    pub(crate) fn check(&self) -> Result<(), VerifyError> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(end) = self.end {
+                if Instant::now() > end {
+                    return Err(VerifyError::LimitExceeded(Limit::Duration));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::Fixture;
+    use super::*;
+
+    fn exceeded(limit: Limit) -> Result<(), VerifyError> {
+        Err(VerifyError::LimitExceeded(limit))
+    }
+
+    #[test]
+    fn rejects_iterations_over_limit() {
+        let f = Fixture::sloth();
+        let limits = |max| VerifyLimits {
+            max_iterations: Some(max),
+            ..VerifyLimits::default()
+        };
+        assert_eq!(
+            f.verify_with_limits(&f.y, &f.proof, &limits(f.iters)),
+            Ok(())
+        );
+        assert_eq!(
+            f.verify_with_limits(&f.y, &f.proof, &limits(f.iters - 1)),
+            exceeded(Limit::Iterations)
+        );
+
+        // squaring 2^40 times back to the seed would take hours
+        let long = Fixture {
+            iters: 1 << 40,
+            ..Fixture::sloth()
+        };
+        assert_eq!(
+            long.verify_with_limits(&long.y, &long.proof, &limits(1 << 20)),
+            exceeded(Limit::Iterations)
+        );
+    }
+
+    #[test]
+    fn rejects_proof_elements_over_limit() {
+        let f = Fixture::pie19();
+        let limits = |max| VerifyLimits {
+            max_proof_elements: Some(max),
+            ..VerifyLimits::default()
+        };
+        let elements = f.proof.pi_list.len();
+        assert_eq!(
+            f.verify_with_limits(&f.y, &f.proof, &limits(elements)),
+            Ok(())
+        );
+        assert_eq!(
+            f.verify_with_limits(&f.y, &f.proof, &limits(elements - 1)),
+            exceeded(Limit::ProofElements)
+        );
+
+        // rejected before the length or any element is looked at
+        let mut long = f.proof.clone();
+        long.pi_list = vec![Integer::new(); 100_000];
+        assert_eq!(
+            f.verify_with_limits(&f.y, &long, &limits(64)),
+            exceeded(Limit::ProofElements)
+        );
+    }
+
+    #[test]
+    fn rejects_elements_over_limit() {
+        let f = Fixture::wes19();
+        let limits = |max| VerifyLimits {
+            max_element_bytes: Some(max),
+            ..VerifyLimits::default()
+        };
+        // elements are below the 512-bit modulus
+        assert_eq!(f.verify_with_limits(&f.y, &f.proof, &limits(64)), Ok(()));
+
+        // rejected before the range check, which would have to compare all of it
+        let huge = Integer::from(1) << 80_000u32;
+        assert_eq!(
+            f.verify_with_limits(&f.y, &huge, &limits(64)),
+            exceeded(Limit::ElementBytes)
+        );
+        assert_eq!(
+            f.verify_with_limits(&huge, &f.proof, &limits(64)),
+            exceeded(Limit::ElementBytes)
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn rejects_verification_over_time_budget() {
+        let f = Fixture::sloth();
+        let limits = |max| VerifyLimits {
+            max_duration: Some(max),
+            ..VerifyLimits::default()
+        };
+        let minute = Duration::from_secs(60);
+        assert_eq!(
+            f.verify_with_limits(&f.y, &f.proof, &limits(minute)),
+            Ok(())
+        );
+
+        // the budget is spent before the first of the 2^40 squarings
+        let long = Fixture {
+            iters: 1 << 40,
+            ..Fixture::sloth()
+        };
+        assert_eq!(
+            long.verify_with_limits(&long.y, &long.proof, &limits(Duration::ZERO)),
+            exceeded(Limit::Duration)
+        );
+    }
+}
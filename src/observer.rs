@@ -0,0 +1,181 @@
+/// Progress reporting and early stopping for the `mine` loops.
+use super::bigint::Integer;
+use super::util;
+use ecvrf;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// What `mine` should do after reporting a step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/// Receives the progress of `mine_with_observer` after every `solve` step.
+pub trait Observer {
+    fn on_step(&mut self, progress: &Progress) -> Control;
+}
+
+/// Observer that ignores the progress and never stops mining.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopObserver;
+
+impl Observer for NoopObserver {
+    fn on_step(&mut self, _progress: &Progress) -> Control {
+        Control::Continue
+    }
+}
+
+impl<F> Observer for F
+where
+    F: FnMut(&Progress) -> Control,
+{
+    fn on_step(&mut self, progress: &Progress) -> Control {
+        self(progress)
+    }
+}
+
+/// The state of a `mine` call after a step. The elapsed time and the state hash are only
+/// computed when asked for, so observers that ignore them cost nothing.
+pub struct Progress<'a> {
+    pub(crate) iters: u64,
+    pub(crate) state: &'a Integer,
+    pub(crate) modulus: &'a Integer,
+    pub(crate) pubkey: &'a ecvrf::VrfPk,
+    pub(crate) stopwatch: &'a Stopwatch,
+}
+
+impl<'a> Progress<'a> {
+    /// Iterations done so far.
+    pub fn iters(&self) -> u64 {
+        self.iters
+    }
+
+    /// Wall-clock time since mining started, always zero on wasm32 which has no clock.
+    pub fn elapsed(&self) -> Duration {
+        self.stopwatch.elapsed()
+    }
+
+    pub fn state(&self) -> &Integer {
+        self.state
+    }
+
+    /// `h_state` of the current state in its canonical form, the value the difficulty check
+    /// hashes.
+    pub fn state_hash(&self) -> Integer {
+        let canonical = util::to_qr_plus(self.modulus, self.state);
+        util::h_state(self.modulus, self.pubkey, &canonical)
+    }
+}
+
+/// Wall-clock time since mining started.
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Stopwatch {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.start.elapsed();
+        #[cfg(target_arch = "wasm32")]
+        return Duration::from_secs(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing;
+    use super::super::{sloth, wes19};
+    use super::*;
+
+    #[test]
+    fn stop_ends_mining_without_solution() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let g = util::h_g(&modulus, &pubkey, &Integer::from(1));
+        // no state meets it
+        let target = Integer::new();
+        let mut seen = Vec::new();
+        let result = wes19::mine_with_observer(
+            4,
+            2,
+            &pubkey,
+            &modulus,
+            &g,
+            &target,
+            &mut |progress: &Progress| {
+                seen.push((
+                    progress.iters(),
+                    progress.state().clone(),
+                    progress.state_hash(),
+                ));
+                if seen.len() == 3 {
+                    Control::Stop
+                } else {
+                    Control::Continue
+                }
+            },
+        );
+        assert_eq!(result, None);
+        assert_eq!(seen.len(), 3);
+
+        let mut state = g;
+        for (i, (iters, seen_state, state_hash)) in seen.into_iter().enumerate() {
+            state = wes19::solve(&modulus, &state, 4, 2, &pubkey, &target).0;
+            assert_eq!(iters, 4 * (i as u64 + 1));
+            assert_eq!(seen_state, state);
+            assert_eq!(state_hash, util::h_state(&modulus, &pubkey, &state));
+        }
+    }
+
+    #[test]
+    fn observer_sees_every_step_up_to_the_solution() {
+        let modulus = testing::prime_modulus();
+        let pubkey = testing::pubkey();
+        let g = util::h_g(&modulus, &pubkey, &Integer::from(1));
+        // about one state in 4 meets it
+        let target = Integer::from(1) << 254u32;
+        let mut seen = Vec::new();
+        let result = sloth::mine_with_observer(
+            1,
+            1,
+            &pubkey,
+            &modulus,
+            &g,
+            &target,
+            &mut |progress: &Progress| {
+                // the hash of the sloth state in the form `solve` checks
+                let solved = util::validate_difficulty(&progress.state_hash(), &target);
+                seen.push((progress.iters(), progress.state().clone(), solved));
+                // stopping at the solution still returns it
+                if solved {
+                    Control::Stop
+                } else {
+                    Control::Continue
+                }
+            },
+        );
+        let (witness, iters) = result.unwrap();
+        assert_eq!(
+            (witness.clone(), iters),
+            sloth::mine(1, 1, &pubkey, &modulus, &g, &target)
+        );
+
+        assert_eq!(seen.len() as u64, iters);
+        for (i, (seen_iters, _, solved)) in seen.iter().enumerate() {
+            assert_eq!(*seen_iters, i as u64 + 1);
+            assert_eq!(*solved, *seen_iters == iters);
+        }
+        assert_eq!(seen[seen.len() - 1].1, witness);
+    }
+}
@@ -1,111 +1,704 @@
+use super::bigint::Integer;
+use super::checkpoint::Checkpoints;
+use super::context::VerifierContext;
+use super::error::VerifyError;
+use super::limits::{Deadline, VerifyLimits, DEADLINE_CHECK_INTERVAL};
+use super::observer::{NoopObserver, Observer};
+use super::report::MiningReport;
+use super::scheme::Pie19;
+use super::squaring::Squarer;
+use super::stream::MiningStream;
 use super::util;
 use ecvrf;
-use rug::Integer;
 use std::vec::Vec;
 
+/// A Pietrzak proof: the midpoints `mu_i` of the halving rounds, which stop once at most
+/// `2^depth` squarings are left for the verifier to do directly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    pub depth: u32,
+    pub pi_list: Vec<Integer>,
+}
+
+/// Remaining number of squarings at which the halving rounds stop.
+fn base_case(depth: u32) -> u64 {
+    1u64.checked_shl(depth).unwrap_or(u64::MAX)
+}
+
+/// The halving rounds of a proof for `iterations` squarings: the number of squarings left at
+/// the start of each round, an odd one being rounded up by squaring y once, and the number
+/// left to the verifier at the end.
+pub fn schedule(iterations: u64, depth: u32) -> (Vec<u64>, u64) {
+    let base = base_case(depth);
+    let mut rounds = Vec::new();
+    let mut t = iterations;
+    while t > base {
+        rounds.push(t);
+        t = t / 2 + t % 2;
+    }
+    (rounds, t)
+}
+
 pub fn mine(
     step: u64,
+    check_interval: u64,
+    depth: u32,
+    pubkey: &ecvrf::VrfPk,
+    modulus: &Integer,
+    ini_state: &Integer,
+    target: &Integer,
+) -> (Integer, Proof, u64) {
+    mine_with_observer(
+        step,
+        check_interval,
+        depth,
+        pubkey,
+        modulus,
+        ini_state,
+        target,
+        &mut NoopObserver,
+    )
+    .expect("mining without an observer does not stop")
+}
+
+/// `mine` reporting its progress to `observer` after every `solve` step. Returns `None` if the
+/// observer stopped it before a solution was found.
+#[allow(clippy::too_many_arguments)]
+pub fn mine_with_observer<O: Observer + ?Sized>(
+    step: u64,
+    check_interval: u64,
+    depth: u32,
+    pubkey: &ecvrf::VrfPk,
+    modulus: &Integer,
+    ini_state: &Integer,
+    target: &Integer,
+    observer: &mut O,
+) -> Option<(Integer, Proof, u64)> {
+    let stream = MiningStream::new(
+        Pie19,
+        step,
+        check_interval,
+        pubkey,
+        modulus,
+        ini_state,
+        target,
+    );
+    let (cur_state, iters) = stream.run(observer)?;
+
+    let pi = prove(modulus, ini_state, &cur_state, iters, depth);
+
+    Some((cur_state, pi, iters))
+}
+
+/// `mine` also returning how many squarings and checks it did and how long it spent solving
+/// and proving.
+pub fn mine_with_report(
+    step: u64,
+    check_interval: u64,
+    depth: u32,
     pubkey: &ecvrf::VrfPk,
     modulus: &Integer,
     ini_state: &Integer,
     target: &Integer,
-) -> (Integer, Vec<Integer>, u64) {
+) -> ((Integer, Proof, u64), MiningReport) {
+    MiningReport::measure(
+        check_interval,
+        |observer| {
+            mine_with_observer(
+                step,
+                check_interval,
+                depth,
+                pubkey,
+                modulus,
+                ini_state,
+                target,
+                observer,
+            )
+        },
+        |&(_, _, iters)| iters,
+    )
+}
+
+/// `mine` keeping at most `max_checkpoints` intermediate states, so the proof is computed by
+/// `prove_with_checkpoints`. About sqrt(T) checkpoints make proving cost about T squarings.
+#[allow(clippy::too_many_arguments)]
+pub fn mine_with_checkpoints(
+    step: u64,
+    check_interval: u64,
+    depth: u32,
+    max_checkpoints: usize,
+    pubkey: &ecvrf::VrfPk,
+    modulus: &Integer,
+    ini_state: &Integer,
+    target: &Integer,
+) -> (Integer, Proof, u64) {
+    let mut checkpoints = Checkpoints::with_budget(modulus, ini_state, max_checkpoints);
     let mut cur_state = ini_state.clone();
     let mut iters: u64 = 0;
+    let mut squarer = Squarer::new(modulus);
 
     loop {
-        iters += step;
-        let (new_state, diff_valid) = solve(modulus, &cur_state, step, pubkey, target);
+        let (new_state, squarings, diff_valid) = solve_with_checkpoints(
+            &mut squarer,
+            &cur_state,
+            step,
+            check_interval,
+            pubkey,
+            target,
+            &mut checkpoints,
+        );
+        iters += squarings;
         cur_state = new_state;
         if diff_valid {
             break;
         }
     }
 
-    let pi = prove(modulus, ini_state, &cur_state, iters);
+    let pi = prove_with_checkpoints(modulus, ini_state, &cur_state, iters, depth, &checkpoints);
 
-    (cur_state.clone(), pi, iters)
+    (cur_state, pi, iters)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn verify(
     modulus: &Integer,
     g: &Integer,
     y: &Integer,
     iterations: u64,
-    pi_list: &Vec<Integer>,
+    check_interval: u64,
+    proof: &Proof,
     pubkey: &ecvrf::VrfPk,
     target: &Integer,
-) -> bool {
-    let hstate = util::h_state(modulus, pubkey, y);
-    if !util::validate_difficulty(&hstate, target) {
-        return false;
+    limits: &VerifyLimits,
+) -> Result<(), VerifyError> {
+    let ctx = VerifierContext::new(modulus, pubkey, target);
+    verify_with_context(&ctx, g, y, iterations, check_interval, proof, limits)
+}
+
+/// `verify` reusing the precomputed data in `ctx`
+pub fn verify_with_context(
+    ctx: &VerifierContext,
+    g: &Integer,
+    y: &Integer,
+    iterations: u64,
+    check_interval: u64,
+    proof: &Proof,
+    limits: &VerifyLimits,
+) -> Result<(), VerifyError> {
+    let deadline = Deadline::start(limits);
+    let modulus = ctx.modulus();
+    let pi_list = &proof.pi_list;
+
+    limits.check_iterations(iterations)?;
+    limits.check_depth(proof.depth)?;
+    limits.check_proof_elements(pi_list.len())?;
+    for x in [g, y].iter().copied().chain(pi_list) {
+        limits.check_element(x)?;
+    }
+    if !util::is_checked_iteration(iterations, check_interval) {
+        return Err(VerifyError::UncheckedIteration);
     }
 
-    let (mut x_i, mut y_i) = (g.clone(), y.clone());
-    let mut t = iterations;
-    let two: Integer = 2u64.into();
+    // the number of rounds is fixed by the statement, reject any other length up front
+    let (rounds, remaining) = schedule(iterations, proof.depth);
+    if pi_list.len() != rounds.len() {
+        return Err(VerifyError::ProofLength {
+            expected: rounds.len(),
+            actual: pi_list.len(),
+        });
+    }
+    // one squaring of y per odd round, and the squarings below the base case
+    let odd_rounds = rounds.iter().filter(|t| !t.is_multiple_of(2)).count() as u64;
+    limits.check_verifier_squarings(remaining.saturating_add(odd_rounds))?;
+
+    // work over QR+_N and reject anything outside of it before exponentiating
+    let g = util::to_qr_plus(modulus, g);
+    util::check_qr_plus(modulus, &g)?;
+    util::check_qr_plus(modulus, y)?;
     for mu_i in pi_list {
-        let r_i = util::hash_fs(modulus, &[&x_i, &y_i, &mu_i]);
+        util::check_qr_plus(modulus, mu_i)?;
+    }
+
+    if !ctx.validate_difficulty(y) {
+        return Err(VerifyError::TargetNotMet);
+    }
+
+    let mut squarer = ctx.squarer();
+    let (mut x_i, mut y_i) = (g, y.clone());
+    for (mu_i, t) in pi_list.iter().zip(rounds) {
+        deadline.check()?;
+        // halving needs an even t, y = x^(2^t) gives y^2 = x^(2^(t+1))
+        if !t.is_multiple_of(2) {
+            squarer.square(&mut y_i);
+            y_i = util::to_qr_plus(modulus, &y_i);
+        }
+
+        let r_i = util::hash_fs(modulus, &[&x_i, &y_i, mu_i]);
 
         let xi_ri = x_i.clone().pow_mod(&r_i, modulus).unwrap();
-        x_i = (xi_ri * mu_i.clone()).div_rem_floor(modulus.clone()).1;
+        x_i = util::to_qr_plus(modulus, &(xi_ri * mu_i.clone()));
 
         let mui_ri = mu_i.clone().pow_mod(&r_i, modulus).unwrap();
-        y_i = (mui_ri * y_i.clone()).div_rem_floor(modulus.clone()).1;
-
-        t = t / 2;
-        if (t % 2 != 0) && (t != 1) {
-            t += 1;
-            y_i = y_i.clone().pow_mod(&two, modulus).unwrap();
-        }
+        y_i = util::to_qr_plus(modulus, &(mui_ri * y_i.clone()));
     }
 
-    y_i == x_i.pow_mod(&two, modulus).unwrap()
+    // finish the remaining y_i = x_i^(2^t) directly
+    let mut remaining = remaining;
+    while remaining > 0 {
+        deadline.check()?;
+        let n = remaining.min(DEADLINE_CHECK_INTERVAL);
+        squarer.square_n(&mut x_i, n);
+        remaining -= n;
+    }
+    if y_i != util::to_qr_plus(modulus, &x_i) {
+        return Err(VerifyError::InvalidProof);
+    }
+    Ok(())
 }
 
+/// Squares `state` up to `step` times, checking the difficulty every `check_interval` squarings
+/// and stopping at the first state that meets the target. Returns that last state, the number
+/// of squarings done and whether it meets the target.
 pub fn solve(
     modulus: &Integer,
     state: &Integer,
     step: u64,
+    check_interval: u64,
     pubkey: &ecvrf::VrfPk,
     target: &Integer,
-) -> (Integer, bool) {
-    let mut y = state.clone();
-    for _ in 0..step {
-        y = y.clone() * y.clone();
-        y = y.div_rem_floor(modulus.clone()).1;
+) -> (Integer, u64, bool) {
+    let mut squarer = Squarer::new(modulus);
+    solve_with_squarer(&mut squarer, state, step, check_interval, pubkey, target)
+}
+
+/// `solve` with the reduction context of a `Squarer` for the modulus, which repeated calls
+/// such as the steps of a `MiningStream` can share instead of building one per step.
+pub fn solve_with_squarer(
+    squarer: &mut Squarer,
+    state: &Integer,
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    target: &Integer,
+) -> (Integer, u64, bool) {
+    util::solve_by_squaring(
+        squarer,
+        state,
+        step,
+        check_interval,
+        pubkey,
+        target,
+        |squarer, y, n| squarer.square_n(y, n),
+    )
+}
+
+/// `solve_with_squarer` recording the intermediate states in `checkpoints`, whose chain must
+/// end at `state`.
+pub fn solve_with_checkpoints(
+    squarer: &mut Squarer,
+    state: &Integer,
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    target: &Integer,
+    checkpoints: &mut Checkpoints,
+) -> (Integer, u64, bool) {
+    util::solve_by_squaring(
+        squarer,
+        state,
+        step,
+        check_interval,
+        pubkey,
+        target,
+        |squarer, y, n| checkpoints.advance(squarer, y, n),
+    )
+}
+
+/// Computes the `depth` midpoints of the halving protocol. Unlike `wes19::prove` it uses no
+/// `FixedBaseTable`, see `fixed_base` for why.
+pub fn prove(modulus: &Integer, g: &Integer, y: &Integer, iterations: u64, depth: u32) -> Proof {
+    prove_by_halving(modulus, g, y, iterations, depth, |squarer, x, half| {
+        // mu_i = x_i^(2^half) by repeated squaring, the exponent is never materialised
+        let mut mu = x.clone();
+        squarer.square_n(&mut mu, half);
+        mu
+    })
+}
+
+/// The halving rounds of `prove`, with `power(squarer, x, h)` computing x^(2^h) for each
+/// midpoint.
+pub(crate) fn prove_by_halving<F>(
+    modulus: &Integer,
+    g: &Integer,
+    y: &Integer,
+    iterations: u64,
+    depth: u32,
+    mut power: F,
+) -> Proof
+where
+    F: FnMut(&mut Squarer, &Integer, u64) -> Integer,
+{
+    let mut squarer = Squarer::new(modulus);
+    let (mut x_i, mut y_i) = (util::to_qr_plus(modulus, g), y.clone());
+    let mut pi_list = Vec::<Integer>::new();
+
+    for t in schedule(iterations, depth).0 {
+        // halving needs an even t, y = x^(2^t) gives y^2 = x^(2^(t+1))
+        if !t.is_multiple_of(2) {
+            squarer.square(&mut y_i);
+            y_i = util::to_qr_plus(modulus, &y_i);
+        }
+        let half = t / 2 + t % 2;
+
+        let mu_i = power(&mut squarer, &x_i, half);
+        let mu_i = util::to_qr_plus(modulus, &mu_i);
+
+        let r_i = util::hash_fs(modulus, &[&x_i, &y_i, &mu_i]);
+
+        let xi_ri = x_i.clone().pow_mod(&r_i, modulus).unwrap();
+        x_i = util::to_qr_plus(modulus, &(xi_ri * mu_i.clone()));
+
+        let mui_ri = mu_i.clone().pow_mod(&r_i, modulus).unwrap();
+        y_i = util::to_qr_plus(modulus, &(mui_ri * y_i.clone()));
+
+        pi_list.push(mu_i);
     }
 
-    let hstate = util::h_state(modulus, pubkey, &y);
-    (y, util::validate_difficulty(&hstate, target))
+    Proof { depth, pi_list }
 }
 
-pub fn prove(modulus: &Integer, g: &Integer, y: &Integer, iterations: u64) -> Vec<Integer> {
-    let (mut x_i, mut y_i) = (g.clone(), y.clone());
+/// `prove` rebuilding the midpoints from the states `g^(2^(j * S))` stored during solving.
+///
+/// Every x_i is a product of terms g^(2^p * e), starting from x_1 = g, so
+/// mu_i = x_i^(2^h) is the product of (g^(2^(p + h)))^e and each g^(2^(p + h)) is at most S
+/// squarings away from a checkpoint. The number of terms doubles every round while h halves,
+/// so the terms are dropped as soon as squaring x_i directly becomes cheaper.
+pub fn prove_with_checkpoints(
+    modulus: &Integer,
+    g: &Integer,
+    y: &Integer,
+    iterations: u64,
+    depth: u32,
+    checkpoints: &Checkpoints,
+) -> Proof {
+    let mut squarer = Squarer::new(modulus);
+    let (mut x_i, mut y_i) = (util::to_qr_plus(modulus, g), y.clone());
     let mut pi_list = Vec::<Integer>::new();
+    // x_i as (p, e) pairs standing for g^(2^p * e), up to sign
+    let mut terms = Some(vec![(0u64, Integer::from(1))]);
 
-    let mut t = iterations;
-    let two: Integer = 2u64.into();
-    while t >= 2 {
-        let two_exp = Integer::from(1) << ((t / 2) as u32); // 2^(t/2)
-        let mu_i = x_i.clone().pow_mod(&two_exp, modulus).unwrap();
+    for t in schedule(iterations, depth).0 {
+        // halving needs an even t, y = x^(2^t) gives y^2 = x^(2^(t+1))
+        if !t.is_multiple_of(2) {
+            squarer.square(&mut y_i);
+            y_i = util::to_qr_plus(modulus, &y_i);
+        }
+        let half = t / 2 + t % 2;
+
+        if let Some(ts) = &terms {
+            if rebuild_cost(checkpoints, ts, half) >= half {
+                terms = None;
+            }
+        }
+        let mu_i = match &terms {
+            Some(ts) => {
+                let mut mu_i = Integer::from(1);
+                for (p, e) in ts {
+                    let g_p = checkpoints.state_at(&mut squarer, p + half);
+                    squarer.mul(&mut mu_i, &g_p.pow_mod(e, modulus).unwrap());
+                }
+                mu_i
+            }
+            None => {
+                let mut mu_i = x_i.clone();
+                squarer.square_n(&mut mu_i, half);
+                mu_i
+            }
+        };
+        let mu_i = util::to_qr_plus(modulus, &mu_i);
 
         let r_i = util::hash_fs(modulus, &[&x_i, &y_i, &mu_i]);
 
         let xi_ri = x_i.clone().pow_mod(&r_i, modulus).unwrap();
-        x_i = (xi_ri * mu_i.clone()).div_rem_floor(modulus.clone()).1;
+        x_i = util::to_qr_plus(modulus, &(xi_ri * mu_i.clone()));
 
         let mui_ri = mu_i.clone().pow_mod(&r_i, modulus).unwrap();
-        y_i = (mui_ri * y_i.clone()).div_rem_floor(modulus.clone()).1;
+        y_i = util::to_qr_plus(modulus, &(mui_ri * y_i.clone()));
 
-        t = t / 2;
-        if (t % 2 != 0) && (t != 1) {
-            t += 1;
-            y_i = y_i.clone().pow_mod(&two, modulus).unwrap();
+        // x_(i+1) = x_i^(r_i) * mu_i
+        if let Some(ts) = &mut terms {
+            let shifted: Vec<_> = ts.iter().map(|(p, e)| (p + half, e.clone())).collect();
+            for (_, e) in ts.iter_mut() {
+                *e *= &r_i;
+            }
+            ts.extend(shifted);
         }
 
         pi_list.push(mu_i);
     }
 
-    pi_list
+    Proof { depth, pi_list }
+}
+
+/// Rough cost in squarings of rebuilding x^(2^h) from `terms`, counting an exponentiation by a
+/// b-bit exponent as 1.2 * b squarings.
+fn rebuild_cost(checkpoints: &Checkpoints, terms: &[(u64, Integer)], h: u64) -> u64 {
+    terms.iter().fold(0u64, |cost, (p, e)| {
+        let exp_cost = u64::from(e.significant_bits()) * 6 / 5;
+        cost.saturating_add(checkpoints.distance(p + h))
+            .saturating_add(exp_cost)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::error::Limit;
+    use super::super::testing::{self, Fixture};
+    use super::*;
+
+    /// An honest proof for `iterations` squarings of `g`, checked every `iterations` steps.
+    fn prove_and_verify(iterations: u64, depth: u32) -> Result<(), VerifyError> {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let g = util::to_qr_plus(&modulus, &util::h_g(&modulus, &pubkey, &Integer::from(1)));
+        let mut y = g.clone();
+        Squarer::new(&modulus).square_n(&mut y, iterations);
+        let y = util::to_qr_plus(&modulus, &y);
+
+        let proof = prove(&modulus, &g, &y, iterations, depth);
+        let limits = VerifyLimits::default();
+        let target = testing::any_target();
+        verify(
+            &modulus, &g, &y, iterations, iterations, &proof, &pubkey, &target, &limits,
+        )
+    }
+
+    #[test]
+    fn proves_any_iteration_count() {
+        for &iterations in &[1, 2, 3, 5, 7, 10, 17, 33, 100, 257, 1000] {
+            for &depth in &[0, 1, 3] {
+                assert_eq!(
+                    prove_and_verify(iterations, depth),
+                    Ok(()),
+                    "T = {}, depth = {}",
+                    iterations,
+                    depth
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn prove_with_checkpoints_matches_prove() {
+        let modulus = testing::modulus();
+        let g = util::to_qr_plus(
+            &modulus,
+            &util::h_g(&modulus, &testing::pubkey(), &Integer::from(1)),
+        );
+        let mut squarer = Squarer::new(&modulus);
+        for &iterations in &[1, 2, 7, 64, 100, 257, 1000] {
+            for &depth in &[0, 1, 3] {
+                // a fixed interval, and budgets that thin the checkpoints as the chain grows
+                let mut all = vec![Checkpoints::new(&modulus, &g, 10)];
+                for &max_states in &[2, 5, 32] {
+                    all.push(Checkpoints::with_budget(&modulus, &g, max_states));
+                }
+                for mut checkpoints in all {
+                    let mut y = g.clone();
+                    checkpoints.advance(&mut squarer, &mut y, iterations);
+                    let y = util::to_qr_plus(&modulus, &y);
+                    assert_eq!(
+                        prove_with_checkpoints(&modulus, &g, &y, iterations, depth, &checkpoints),
+                        prove(&modulus, &g, &y, iterations, depth),
+                        "T = {}, depth = {}, interval = {}",
+                        iterations,
+                        depth,
+                        checkpoints.interval()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mine_with_checkpoints_matches_mine() {
+        let f = Fixture::pie19();
+        assert_eq!(
+            mine_with_checkpoints(8, 8, 1, 4, &f.pubkey, &f.modulus, &f.g, &f.target),
+            (f.y, f.proof, f.iters)
+        );
+    }
+
+    #[test]
+    fn schedule_halves_large_iteration_counts() {
+        for &iterations in &[(1 << 33) + 1, (1 << 40) - 1, u64::MAX] {
+            for &depth in &[0, 1, 3, 20] {
+                let (rounds, remaining) = schedule(iterations, depth);
+                assert_eq!(rounds[0], iterations);
+                for pair in rounds.windows(2) {
+                    assert_eq!(pair[1], pair[0] / 2 + pair[0] % 2);
+                }
+                let last = rounds[rounds.len() - 1];
+                assert_eq!(remaining, last / 2 + last % 2);
+                assert!(remaining <= 1 << depth && last > 1 << depth);
+            }
+        }
+    }
+
+    #[cfg(feature = "trapdoor")]
+    #[test]
+    fn proves_beyond_2_pow_33_iterations() {
+        use super::super::trapdoor::Trapdoor;
+
+        let (p, q) = testing::factors();
+        let trapdoor = Trapdoor::new(p, q);
+        let modulus = trapdoor.modulus();
+        let pubkey = testing::pubkey();
+        let g = util::to_qr_plus(modulus, &util::h_g(modulus, &pubkey, &Integer::from(1)));
+        let iterations = (1 << 34) + 3;
+        let y = util::to_qr_plus(modulus, &trapdoor.eval(&g, iterations));
+
+        let proof = trapdoor.pie19_prove(&g, &y, iterations, 3);
+        assert_eq!(proof.pi_list.len(), schedule(iterations, 3).0.len());
+        let limits = VerifyLimits::default();
+        let target = testing::any_target();
+        assert_eq!(
+            verify(modulus, &g, &y, iterations, iterations, &proof, &pubkey, &target, &limits,),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_proof_of_wrong_length() {
+        let f = Fixture::pie19();
+        // depth 1 over 8 iterations halves twice, from 8 and from 4
+        let expected = f.proof.pi_list.len();
+        assert_eq!(expected, 2);
+
+        let mut short = f.proof.clone();
+        short.pi_list.pop();
+        assert_eq!(
+            f.verify(&f.y, &short),
+            Err(VerifyError::ProofLength {
+                expected,
+                actual: expected - 1
+            })
+        );
+
+        let mut long = f.proof.clone();
+        long.pi_list.push(f.y.clone());
+        assert_eq!(
+            f.verify(&f.y, &long),
+            Err(VerifyError::ProofLength {
+                expected,
+                actual: expected + 1
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_depth_over_limit() {
+        let f = Fixture::pie19();
+        let limits = VerifyLimits {
+            max_depth: Some(8),
+            ..VerifyLimits::default()
+        };
+        // no rounds at all, the verifier would square y all the way down to g
+        let proof = Proof {
+            depth: 63,
+            pi_list: Vec::new(),
+        };
+        assert_eq!(
+            f.verify_with_limits(&f.y, &proof, &limits),
+            Err(VerifyError::LimitExceeded(Limit::Depth))
+        );
+        assert_eq!(f.verify_with_limits(&f.y, &f.proof, &limits), Ok(()));
+    }
+
+    #[test]
+    fn rejects_verifier_squarings_over_limit() {
+        let f = Fixture::pie19();
+        // depth 1 over 8 iterations leaves 2 squarings to the verifier
+        let verify_with_max = |max| {
+            let limits = VerifyLimits {
+                max_verifier_squarings: Some(max),
+                ..VerifyLimits::default()
+            };
+            f.verify_with_limits(&f.y, &f.proof, &limits)
+        };
+        assert_eq!(
+            verify_with_max(1),
+            Err(VerifyError::LimitExceeded(Limit::VerifierSquarings))
+        );
+        assert_eq!(verify_with_max(2), Ok(()));
+    }
+
+    #[test]
+    fn accepts_honest_solution() {
+        let f = Fixture::pie19();
+        assert_eq!(f.verify(&f.y, &f.proof), Ok(()));
+    }
+
+    #[test]
+    fn rejects_elements_outside_qr_plus() {
+        let f = Fixture::pie19();
+        f.assert_rejects_outside_qr_plus(|mu| {
+            let mut proof = f.proof.clone();
+            proof.pi_list[0] = mu;
+            proof
+        });
+    }
+
+    #[test]
+    fn rejects_state_missing_target() {
+        let f = Fixture {
+            target: Integer::new(),
+            ..Fixture::pie19()
+        };
+        assert_eq!(f.verify(&f.y, &f.proof), Err(VerifyError::TargetNotMet));
+    }
+
+    #[test]
+    fn rejects_invalid_proof() {
+        let f = Fixture::pie19();
+        for i in 0..f.proof.pi_list.len() {
+            let mut proof = f.proof.clone();
+            proof.pi_list[i] = testing::qr_plus_element(&f.modulus);
+            assert_ne!(proof, f.proof);
+            assert_eq!(
+                f.verify(&f.y, &proof),
+                Err(VerifyError::InvalidProof),
+                "mu_{}",
+                i
+            );
+        }
+
+        // an honest proof for another seed's g
+        let other_seed = Fixture {
+            seed: Integer::from(2),
+            ..Fixture::pie19()
+        };
+        assert_eq!(
+            other_seed.verify(&other_seed.y, &other_seed.proof),
+            Err(VerifyError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn rejects_unchecked_iteration() {
+        for &(iters, check_interval) in &[(8, 3), (8, 16), (8, 0), (0, 8)] {
+            let f = Fixture {
+                iters,
+                check_interval,
+                ..Fixture::pie19()
+            };
+            assert_eq!(
+                f.verify(&f.y, &f.proof),
+                Err(VerifyError::UncheckedIteration),
+                "{} iterations, checked every {}",
+                iters,
+                check_interval
+            );
+        }
+    }
 }
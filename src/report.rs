@@ -0,0 +1,140 @@
+/// Timing and throughput statistics of a `mine` call.
+use super::observer::{Control, Observer, Progress, Stopwatch};
+use std::fmt;
+use std::time::Duration;
+
+/// What `mine_with_report` spent its time on. The times are zero on wasm32, which has no clock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MiningReport {
+    /// Squarings done while solving, square roots for sloth.
+    pub squarings: u64,
+    /// Difficulty checks done while solving.
+    pub checks: u64,
+    pub solve_time: Duration,
+    pub prove_time: Duration,
+}
+
+impl MiningReport {
+    /// Runs `mine`, a `mine_with_observer` call, and times it. The observer sees the last step
+    /// right before the proof is computed, which splits solving from proving. `iters` picks the
+    /// iterations out of the result.
+    pub(crate) fn measure<T, M, I>(check_interval: u64, mine: M, iters: I) -> (T, MiningReport)
+    where
+        M: FnOnce(&mut dyn Observer) -> Option<T>,
+        I: FnOnce(&T) -> u64,
+    {
+        let stopwatch = Stopwatch::start();
+        let mut solve_time = Duration::from_secs(0);
+        let mut observer = |_: &Progress| {
+            solve_time = stopwatch.elapsed();
+            Control::Continue
+        };
+        let result = mine(&mut observer).expect("mining without an observer does not stop");
+        let total_time = stopwatch.elapsed();
+
+        let iters = iters(&result);
+        let report = MiningReport {
+            squarings: iters,
+            checks: iters / check_interval,
+            solve_time,
+            prove_time: total_time - solve_time,
+        };
+        (result, report)
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.solve_time + self.prove_time
+    }
+
+    /// Solving throughput, zero if no time was measured.
+    pub fn squarings_per_second(&self) -> f64 {
+        let secs = self.solve_time.as_secs_f64();
+        if secs > 0.0 {
+            self.squarings as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+impl fmt::Display for MiningReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} squarings, {} checks, solve {:?}, prove {:?}, {:.0} squarings/s",
+            self.squarings,
+            self.checks,
+            self.solve_time,
+            self.prove_time,
+            self.squarings_per_second()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bigint::Integer;
+    use super::super::testing;
+    use super::super::{pie19, sloth, util, wes19};
+    use super::*;
+
+    const STEP: u64 = 4;
+    const CHECK_INTERVAL: u64 = 2;
+
+    // about one state in 4 meets it
+    fn target() -> Integer {
+        Integer::from(1) << 254u32
+    }
+
+    fn assert_counts(report: &MiningReport, iters: u64) {
+        assert_eq!(report.squarings, iters);
+        assert_eq!(report.checks, iters / CHECK_INTERVAL);
+        assert_eq!(report.total_time(), report.solve_time + report.prove_time);
+    }
+
+    #[test]
+    fn reports_the_mined_solution() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let target = target();
+        let g = util::h_g(&modulus, &pubkey, &Integer::from(1));
+
+        let (mined, report) =
+            wes19::mine_with_report(STEP, CHECK_INTERVAL, &pubkey, &modulus, &g, &target);
+        assert_counts(&report, mined.2);
+        assert_eq!(
+            mined,
+            wes19::mine(STEP, CHECK_INTERVAL, &pubkey, &modulus, &g, &target)
+        );
+
+        let (mined, report) =
+            pie19::mine_with_report(STEP, CHECK_INTERVAL, 1, &pubkey, &modulus, &g, &target);
+        assert_counts(&report, mined.2);
+        assert_eq!(
+            mined,
+            pie19::mine(STEP, CHECK_INTERVAL, 1, &pubkey, &modulus, &g, &target)
+        );
+
+        let modulus = testing::prime_modulus();
+        let g = util::h_g(&modulus, &pubkey, &Integer::from(1));
+        let (mined, report) =
+            sloth::mine_with_report(STEP, CHECK_INTERVAL, &pubkey, &modulus, &g, &target);
+        assert_counts(&report, mined.1);
+        assert_eq!(
+            mined,
+            sloth::mine(STEP, CHECK_INTERVAL, &pubkey, &modulus, &g, &target)
+        );
+    }
+
+    #[test]
+    fn squarings_per_second_needs_a_solve_time() {
+        let mut report = MiningReport {
+            squarings: 1000,
+            checks: 10,
+            ..MiningReport::default()
+        };
+        assert_eq!(report.squarings_per_second(), 0.0);
+        report.solve_time = Duration::from_millis(500);
+        assert_eq!(report.squarings_per_second(), 2000.0);
+    }
+}
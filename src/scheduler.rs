@@ -0,0 +1,378 @@
+/// Mining several candidate seeds at once, e.g. one per competing tip.
+use super::bigint::Integer;
+use super::scheme::Scheme;
+use super::stream::MiningStream;
+use super::util;
+use ecvrf;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::cmp::Reverse;
+
+/// Identifies a job within its `Scheduler`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(u64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Paused,
+    Solved,
+}
+
+/// A state meeting the target of a job, with what proving it needs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub job: JobId,
+    pub ini_state: Integer,
+    pub state: Integer,
+    pub iters: u64,
+}
+
+struct Job<'a, S> {
+    id: JobId,
+    priority: u32,
+    status: JobStatus,
+    // when the job last ran, counted in jobs run, equal priorities take turns by it
+    last_run: u64,
+    ini_state: Integer,
+    stream: MiningStream<'a, S>,
+}
+
+impl<'a, S: Scheme> Job<'a, S> {
+    fn advance(&mut self) -> Option<Solution> {
+        let (state, iters, diff_valid) = self.stream.next()?;
+        if !diff_valid {
+            return None;
+        }
+        self.status = JobStatus::Solved;
+        Some(Solution {
+            job: self.id,
+            ini_state: self.ini_state.clone(),
+            state,
+            iters,
+        })
+    }
+}
+
+/// Keeps a sequential chain alive for each of several `(seed, pubkey, target)` jobs and runs
+/// `solve` steps on the `workers` highest-priority running jobs at a time, equal priorities
+/// taking turns. Paused jobs keep their state and continue where they stopped when resumed.
+///
+/// With the `parallel` feature the selected jobs of a step run on a pool of `workers` threads
+/// that the scheduler starts once and keeps until dropped. Without it, on wasm32, or with a
+/// single worker they run one after the other on the calling thread.
+pub struct Scheduler<'a, S> {
+    scheme: S,
+    modulus: &'a Integer,
+    step: u64,
+    check_interval: u64,
+    workers: usize,
+    #[cfg(feature = "parallel")]
+    pool: Option<rayon::ThreadPool>,
+    next_id: u64,
+    tick: u64,
+    jobs: Vec<Job<'a, S>>,
+}
+
+impl<'a, S: Scheme + Clone + Send> Scheduler<'a, S> {
+    pub fn new(
+        scheme: S,
+        modulus: &'a Integer,
+        step: u64,
+        check_interval: u64,
+        workers: usize,
+    ) -> Self {
+        util::assert_check_interval(step, check_interval);
+        assert!(workers > 0, "scheduler must run at least one job at a time");
+        // wasm32 has no threads to start
+        #[cfg(feature = "parallel")]
+        let pool = if workers > 1 && cfg!(not(target_arch = "wasm32")) {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(workers)
+                .build()
+                .expect("failed to start the scheduler threads");
+            Some(pool)
+        } else {
+            None
+        };
+        Scheduler {
+            scheme,
+            modulus,
+            step,
+            check_interval,
+            workers,
+            #[cfg(feature = "parallel")]
+            pool,
+            next_id: 0,
+            tick: 0,
+            jobs: Vec::new(),
+        }
+    }
+
+    /// Starts mining from `h_g(seed)`, jobs with a higher `priority` are run first.
+    pub fn add_job(
+        &mut self,
+        seed: &Integer,
+        pubkey: &'a ecvrf::VrfPk,
+        target: &'a Integer,
+        priority: u32,
+    ) -> JobId {
+        let id = JobId(self.next_id);
+        self.next_id += 1;
+        let ini_state = util::h_g(self.modulus, pubkey, seed);
+        let stream = MiningStream::new(
+            self.scheme.clone(),
+            self.step,
+            self.check_interval,
+            pubkey,
+            self.modulus,
+            &ini_state,
+            target,
+        );
+        self.jobs.push(Job {
+            id,
+            priority,
+            status: JobStatus::Running,
+            last_run: 0,
+            ini_state,
+            stream,
+        });
+        id
+    }
+
+    /// Drops a job and its state, returns whether it existed.
+    pub fn remove_job(&mut self, id: JobId) -> bool {
+        let len = self.jobs.len();
+        self.jobs.retain(|job| job.id != id);
+        self.jobs.len() != len
+    }
+
+    /// Stops running a job until `resume`, returns whether it was running.
+    pub fn pause(&mut self, id: JobId) -> bool {
+        self.set_status(id, JobStatus::Running, JobStatus::Paused)
+    }
+
+    /// Returns whether the job was paused.
+    pub fn resume(&mut self, id: JobId) -> bool {
+        self.set_status(id, JobStatus::Paused, JobStatus::Running)
+    }
+
+    /// Returns whether the job exists.
+    pub fn set_priority(&mut self, id: JobId, priority: u32) -> bool {
+        self.job_mut(id)
+            .map(|job| job.priority = priority)
+            .is_some()
+    }
+
+    pub fn status(&self, id: JobId) -> Option<JobStatus> {
+        self.job(id).map(|job| job.status)
+    }
+
+    /// The current state of a job and the iterations done so far.
+    pub fn progress(&self, id: JobId) -> Option<(&Integer, u64)> {
+        self.job(id)
+            .map(|job| (job.stream.state(), job.stream.iters()))
+    }
+
+    /// Runs one step of the selected jobs and returns the solutions found by it.
+    pub fn step(&mut self) -> Vec<Solution> {
+        let mut selected: Vec<&mut Job<'a, S>> = self
+            .jobs
+            .iter_mut()
+            .filter(|job| job.status == JobStatus::Running)
+            .collect();
+        selected.sort_by_key(|job| (Reverse(job.priority), job.last_run, job.id));
+        selected.truncate(self.workers);
+        // one tick per job rather than per step, so that the jobs left out of a step come
+        // before every job of it next time
+        for job in selected.iter_mut() {
+            self.tick += 1;
+            job.last_run = self.tick;
+        }
+
+        #[cfg(feature = "parallel")]
+        if let Some(pool) = self.pool.as_ref().filter(|_| selected.len() > 1) {
+            return pool.install(|| {
+                selected
+                    .into_par_iter()
+                    .filter_map(|job| job.advance())
+                    .collect()
+            });
+        }
+        selected
+            .into_iter()
+            .filter_map(|job| job.advance())
+            .collect()
+    }
+
+    /// Runs steps until one finds a solution, or returns an empty list once no job is running.
+    pub fn run_until_solution(&mut self) -> Vec<Solution> {
+        while self.jobs.iter().any(|job| job.status == JobStatus::Running) {
+            let solutions = self.step();
+            if !solutions.is_empty() {
+                return solutions;
+            }
+        }
+        Vec::new()
+    }
+
+    fn job(&self, id: JobId) -> Option<&Job<'a, S>> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    fn job_mut(&mut self, id: JobId) -> Option<&mut Job<'a, S>> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    fn set_status(&mut self, id: JobId, from: JobStatus, to: JobStatus) -> bool {
+        match self.job_mut(id) {
+            Some(job) if job.status == from => {
+                job.status = to;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::scheme::Wes19;
+    use super::super::testing;
+    use super::super::wes19;
+    use super::*;
+
+    const STEP: u64 = 4;
+
+    /// A target no state meets, so that jobs run until removed.
+    fn unreachable() -> Integer {
+        Integer::new()
+    }
+
+    fn iters(scheduler: &Scheduler<Wes19>, id: JobId) -> u64 {
+        scheduler.progress(id).unwrap().1
+    }
+
+    #[test]
+    fn runs_highest_priority_first() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let target = unreachable();
+        let mut scheduler = Scheduler::new(Wes19, &modulus, STEP, STEP, 1);
+        let low = scheduler.add_job(&Integer::from(1), &pubkey, &target, 1);
+        let high = scheduler.add_job(&Integer::from(2), &pubkey, &target, 5);
+
+        for _ in 0..3 {
+            assert!(scheduler.step().is_empty());
+        }
+        assert_eq!(iters(&scheduler, high), 3 * STEP);
+        assert_eq!(iters(&scheduler, low), 0);
+
+        scheduler.set_priority(low, 9);
+        scheduler.step();
+        assert_eq!(iters(&scheduler, low), STEP);
+    }
+
+    #[test]
+    fn equal_priorities_take_turns() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let target = unreachable();
+        let mut scheduler = Scheduler::new(Wes19, &modulus, STEP, STEP, 2);
+        let ids: Vec<JobId> = (0..3)
+            .map(|seed| scheduler.add_job(&Integer::from(seed), &pubkey, &target, 0))
+            .collect();
+
+        // 2 of the 3 jobs per step, so 3 steps give every job 2 turns
+        for _ in 0..3 {
+            scheduler.step();
+        }
+        for &id in &ids {
+            assert_eq!(iters(&scheduler, id), 2 * STEP);
+        }
+    }
+
+    #[test]
+    fn paused_jobs_keep_their_state() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let target = unreachable();
+        let mut scheduler = Scheduler::new(Wes19, &modulus, STEP, STEP, 1);
+        let paused = scheduler.add_job(&Integer::from(1), &pubkey, &target, 5);
+        let other = scheduler.add_job(&Integer::from(2), &pubkey, &target, 0);
+
+        scheduler.step();
+        let state = scheduler.progress(paused).unwrap().0.clone();
+        assert!(scheduler.pause(paused));
+        assert!(!scheduler.pause(paused));
+        assert_eq!(scheduler.status(paused), Some(JobStatus::Paused));
+
+        // the other job runs meanwhile, the paused one does not move
+        scheduler.step();
+        scheduler.step();
+        assert_eq!(iters(&scheduler, other), 2 * STEP);
+        assert_eq!(scheduler.progress(paused), Some((&state, STEP)));
+
+        assert!(scheduler.resume(paused));
+        assert!(!scheduler.resume(paused));
+        scheduler.step();
+        let (expected, _, _) = wes19::solve(&modulus, &state, STEP, STEP, &pubkey, &target);
+        assert_eq!(scheduler.progress(paused), Some((&expected, 2 * STEP)));
+    }
+
+    #[test]
+    fn solved_jobs_stop() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let target = testing::any_target();
+        let mut scheduler = Scheduler::new(Wes19, &modulus, STEP, STEP, 1);
+        let id = scheduler.add_job(&Integer::from(1), &pubkey, &target, 0);
+
+        let solutions = scheduler.run_until_solution();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].job, id);
+        assert_eq!(solutions[0].iters, STEP);
+        assert_eq!(scheduler.status(id), Some(JobStatus::Solved));
+        assert!(scheduler.step().is_empty());
+        assert!(scheduler.run_until_solution().is_empty());
+
+        assert!(scheduler.remove_job(id));
+        assert!(!scheduler.remove_job(id));
+        assert_eq!(scheduler.status(id), None);
+    }
+
+    #[test]
+    fn pooled_jobs_advance_like_lone_ones() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let target = testing::any_target() >> 3;
+        let seeds: Vec<Integer> = (0..4).map(Integer::from).collect();
+        let run = |seeds: &[Integer]| {
+            let mut scheduler = Scheduler::new(Wes19, &modulus, STEP, STEP, seeds.len());
+            let ids: Vec<JobId> = seeds
+                .iter()
+                .map(|seed| scheduler.add_job(seed, &pubkey, &target, 0))
+                .collect();
+            let mut solutions = Vec::new();
+            for _ in 0..8 {
+                solutions.extend(scheduler.step());
+            }
+            ids.iter()
+                .map(|&id| {
+                    let solution = solutions
+                        .iter()
+                        .find(|solution| solution.job == id)
+                        .map(|solution| (solution.state.clone(), solution.iters));
+                    let (state, iters) = scheduler.progress(id).unwrap();
+                    (solution, state.clone(), iters)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let lone: Vec<_> = seeds
+            .iter()
+            .flat_map(|seed| run(std::slice::from_ref(seed)))
+            .collect();
+        assert_eq!(run(&seeds), lone);
+    }
+}
@@ -0,0 +1,233 @@
+/// The SeqPoW schemes as types, for code that is generic over the scheme.
+use super::bigint::Integer;
+use super::context::VerifierContext;
+use super::error::VerifyError;
+use super::limits::VerifyLimits;
+use super::squaring::Squarer;
+use super::{pie19, sloth, wes19};
+use ecvrf;
+
+/// Operations every SeqPoW scheme provides.
+pub trait Scheme {
+    /// What a miner publishes besides the final state so that others can check it.
+    type Proof;
+
+    /// One mining step, see `sloth::solve`, `wes19::solve` and `pie19::solve`.
+    fn solve(
+        &self,
+        modulus: &Integer,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool);
+
+    /// `solve` over the modulus of `squarer`, reusing its reduction context where the scheme
+    /// squares, so that callers running many steps build it once.
+    fn solve_with_squarer(
+        &self,
+        squarer: &mut Squarer,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool) {
+        self.solve(
+            squarer.modulus(),
+            state,
+            step,
+            check_interval,
+            pubkey,
+            target,
+        )
+    }
+
+    /// Checks that `state` is the first solution after `iterations` steps from the seed's
+    /// starting state, see `sloth::verify_with_context`, `wes19::verify_with_context` and
+    /// `pie19::verify_with_context`.
+    #[allow(clippy::too_many_arguments)]
+    fn verify(
+        &self,
+        ctx: &VerifierContext,
+        seed: &Integer,
+        iterations: u64,
+        check_interval: u64,
+        state: &Integer,
+        proof: &Self::Proof,
+        limits: &VerifyLimits,
+    ) -> Result<(), VerifyError>;
+}
+
+/// Modular square roots, see `sloth`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sloth;
+
+/// Repeated squaring with Wesolowski proofs, see `wes19`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Wes19;
+
+/// Repeated squaring with Pietrzak proofs, see `pie19`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pie19;
+
+impl Scheme for Sloth {
+    type Proof = ();
+
+    fn solve(
+        &self,
+        modulus: &Integer,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool) {
+        sloth::solve(modulus, state, step, check_interval, pubkey, target)
+    }
+
+    fn verify(
+        &self,
+        ctx: &VerifierContext,
+        seed: &Integer,
+        iterations: u64,
+        check_interval: u64,
+        state: &Integer,
+        _proof: &Self::Proof,
+        limits: &VerifyLimits,
+    ) -> Result<(), VerifyError> {
+        sloth::verify_with_context(ctx, seed, iterations, check_interval, state, limits)
+    }
+}
+
+impl Scheme for Wes19 {
+    type Proof = Integer;
+
+    fn solve(
+        &self,
+        modulus: &Integer,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool) {
+        wes19::solve(modulus, state, step, check_interval, pubkey, target)
+    }
+
+    fn solve_with_squarer(
+        &self,
+        squarer: &mut Squarer,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool) {
+        wes19::solve_with_squarer(squarer, state, step, check_interval, pubkey, target)
+    }
+
+    fn verify(
+        &self,
+        ctx: &VerifierContext,
+        seed: &Integer,
+        iterations: u64,
+        check_interval: u64,
+        state: &Integer,
+        proof: &Self::Proof,
+        limits: &VerifyLimits,
+    ) -> Result<(), VerifyError> {
+        let g = ctx.h_g(seed);
+        wes19::verify_with_context(ctx, &g, iterations, check_interval, state, proof, limits)
+    }
+}
+
+impl Scheme for Pie19 {
+    type Proof = pie19::Proof;
+
+    fn solve(
+        &self,
+        modulus: &Integer,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool) {
+        pie19::solve(modulus, state, step, check_interval, pubkey, target)
+    }
+
+    fn solve_with_squarer(
+        &self,
+        squarer: &mut Squarer,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool) {
+        pie19::solve_with_squarer(squarer, state, step, check_interval, pubkey, target)
+    }
+
+    fn verify(
+        &self,
+        ctx: &VerifierContext,
+        seed: &Integer,
+        iterations: u64,
+        check_interval: u64,
+        state: &Integer,
+        proof: &Self::Proof,
+        limits: &VerifyLimits,
+    ) -> Result<(), VerifyError> {
+        let g = ctx.h_g(seed);
+        pie19::verify_with_context(ctx, &g, state, iterations, check_interval, proof, limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing;
+    use super::super::util;
+    use super::*;
+
+    /// Checks that one `solve` call of a long `step` stops at the first checked state meeting
+    /// the target, the one a chain of single-check calls reaches first.
+    fn assert_stops_at_first_solution<S: Scheme>(scheme: S, modulus: &Integer) {
+        let pubkey = testing::pubkey();
+        // about one state in 4 meets it, so 64 checks all fail once in 10^8 runs
+        let target = Integer::from(1) << 254u32;
+        let (step, check_interval) = (256, 4);
+        let g = util::h_g(modulus, &pubkey, &Integer::from(1));
+
+        let (mut state, mut iters) = (g.clone(), 0);
+        loop {
+            let (next, squarings, diff_valid) = scheme.solve(
+                modulus,
+                &state,
+                check_interval,
+                check_interval,
+                &pubkey,
+                &target,
+            );
+            state = next;
+            iters += squarings;
+            if diff_valid {
+                break;
+            }
+        }
+        assert!(iters < step);
+
+        assert_eq!(
+            scheme.solve(modulus, &g, step, check_interval, &pubkey, &target),
+            (state, iters, true)
+        );
+    }
+
+    #[test]
+    fn solve_stops_at_first_solution() {
+        assert_stops_at_first_solution(Sloth, &testing::prime_modulus());
+        assert_stops_at_first_solution(Wes19, &testing::modulus());
+        assert_stops_at_first_solution(Pie19, &testing::modulus());
+    }
+}
@@ -1,85 +1,290 @@
 /// Modular Square Roots-based Sequential Proof-of-Work (SeqPoW) implementation.
+use super::bigint::Integer;
+use super::context::VerifierContext;
+use super::error::VerifyError;
+use super::limits::{Deadline, VerifyLimits, DEADLINE_CHECK_INTERVAL};
+use super::observer::{NoopObserver, Observer};
+use super::report::MiningReport;
+use super::scheme::Sloth;
+use super::stream::MiningStream;
 use super::util;
 use ecvrf;
-use rug::Integer;
 
 pub fn mine(
     step: u64,
+    check_interval: u64,
     pubkey: &ecvrf::VrfPk,
     modulus: &Integer,
     ini_state: &Integer,
     target: &Integer,
 ) -> (Integer, u64) {
-    let mut cur_state = ini_state.clone();
-    let mut iters: u64 = 0;
-
-    loop {
-        iters += step;
-        let (new_state, diff_valid) = solve(modulus, &cur_state, step, pubkey, target);
-        cur_state = new_state;
-        if diff_valid {
-            break;
-        }
-    }
+    mine_with_observer(
+        step,
+        check_interval,
+        pubkey,
+        modulus,
+        ini_state,
+        target,
+        &mut NoopObserver,
+    )
+    .expect("mining without an observer does not stop")
+}
 
-    (cur_state.clone(), iters)
+/// `mine` reporting its progress to `observer` after every `solve` step. Returns `None` if the
+/// observer stopped it before a solution was found.
+pub fn mine_with_observer<O: Observer + ?Sized>(
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    modulus: &Integer,
+    ini_state: &Integer,
+    target: &Integer,
+    observer: &mut O,
+) -> Option<(Integer, u64)> {
+    MiningStream::new(
+        Sloth,
+        step,
+        check_interval,
+        pubkey,
+        modulus,
+        ini_state,
+        target,
+    )
+    .run(observer)
+}
+
+/// `mine` also returning how many squarings and checks it did and how long it spent solving
+/// and proving.
+pub fn mine_with_report(
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    modulus: &Integer,
+    ini_state: &Integer,
+    target: &Integer,
+) -> ((Integer, u64), MiningReport) {
+    MiningReport::measure(
+        check_interval,
+        |observer| {
+            mine_with_observer(
+                step,
+                check_interval,
+                pubkey,
+                modulus,
+                ini_state,
+                target,
+                observer,
+            )
+        },
+        |&(_, iters)| iters,
+    )
 }
 
+/// Takes up to `step` square roots of `state`, checking the difficulty every `check_interval`
+/// of them and stopping at the first state that meets the target. Returns that last state, the
+/// number of square roots taken and whether it meets the target.
 pub fn solve(
     modulus: &Integer,
     state: &Integer,
     step: u64,
+    check_interval: u64,
     pubkey: &ecvrf::VrfPk,
     target: &Integer,
-) -> (Integer, bool) {
+) -> (Integer, u64, bool) {
+    util::assert_check_interval(step, check_interval);
+
     // Take state by moduli p
-    let mut new_state = Integer::from(state.clone().div_rem_floor(modulus.clone()).1);
+    let mut new_state = state.clone().div_rem_floor(modulus.clone()).1;
 
     // Exponent for square root calculation
     let exponent = (modulus.clone() + 1) / 4;
 
-    for _ in 0..step {
+    for i in 1..=step {
         // Perform a slow modular square root extraction
         new_state.pow_mod_mut(&exponent, modulus).unwrap();
         // println!("new_state:\t\t0x{:064x}", new_state);
+
+        if i % check_interval == 0 {
+            // squaring back only recovers a state up to sign, so hash the form both signs share
+            let canonical = util::to_qr_plus(modulus, &new_state);
+            let hstate = util::h_state(modulus, pubkey, &canonical);
+            if util::validate_difficulty(&hstate, target) {
+                return (new_state, i, true);
+            }
+        }
     }
 
-    let hstate = util::h_state(modulus, pubkey, &new_state);
-    (new_state, util::validate_difficulty(&hstate, target))
+    (new_state, step, false)
 }
 
 /// Verifies that mining function from given `seed` was calculated and produced a `witness`
+#[allow(clippy::too_many_arguments)]
 pub fn verify(
     modulus: &Integer,
     seed: &Integer,
     total_num_steps: u64,
+    check_interval: u64,
     witness: &Integer,
     pubkey: &ecvrf::VrfPk,
     target: &Integer,
-) -> bool {
-    let hstate = util::h_state(modulus, pubkey, witness);
-    if !util::validate_difficulty(&hstate, target) {
-        return false;
+    limits: &VerifyLimits,
+) -> Result<(), VerifyError> {
+    let ctx = VerifierContext::new(modulus, pubkey, target);
+    verify_with_context(&ctx, seed, total_num_steps, check_interval, witness, limits)
+}
+
+/// `verify` reusing the precomputed data in `ctx`
+pub fn verify_with_context(
+    ctx: &VerifierContext,
+    seed: &Integer,
+    total_num_steps: u64,
+    check_interval: u64,
+    witness: &Integer,
+    limits: &VerifyLimits,
+) -> Result<(), VerifyError> {
+    let deadline = Deadline::start(limits);
+    limits.check_iterations(total_num_steps)?;
+    // the verifier squares the whole chain back to the seed
+    limits.check_verifier_squarings(total_num_steps)?;
+    limits.check_element(seed)?;
+    limits.check_element(witness)?;
+    if !util::is_checked_iteration(total_num_steps, check_interval) {
+        return Err(VerifyError::UncheckedIteration);
     }
 
-    // Get instance of 2 in Integer format for performing of squares
-    let square: Integer = 2u64.into();
+    let modulus = ctx.modulus();
+    if !ctx.validate_difficulty(&util::to_qr_plus(modulus, witness)) {
+        return Err(VerifyError::TargetNotMet);
+    }
 
-    let mut cur_state = witness.clone();
+    let mut squarer = ctx.squarer();
+    let mut cur_state = witness.clone().div_rem_floor(modulus.clone()).1;
     // Perform NUM_ITERS of sequential modular squares to perform a verification of the solution
-    for _ in 0..total_num_steps {
+    for i in 0..total_num_steps {
+        if i % DEADLINE_CHECK_INTERVAL == 0 {
+            deadline.check()?;
+        }
         // Perform a simple and fast modular squaring
-        cur_state.pow_mod_mut(&square, modulus).unwrap();
+        squarer.square(&mut cur_state);
 
-        let cur_state_inv = (-cur_state.clone()).div_rem_floor(modulus.clone()).1;
-
-        if util::validate_difficulty(&util::h_state(modulus, pubkey, &cur_state), target)
-            && util::validate_difficulty(&util::h_state(modulus, pubkey, &cur_state_inv), target)
+        // squaring walks the chain backwards, from the witness down to g, and only the states
+        // `mine` checked can be earlier solutions. cur_state is the mined state up to sign,
+        // `solve` hashed the form both signs share.
+        let iteration = total_num_steps - i - 1;
+        if iteration > 0
+            && iteration.is_multiple_of(check_interval)
+            && ctx.validate_difficulty(&util::to_qr_plus(modulus, &cur_state))
         {
-            return false;
+            return Err(VerifyError::EarlierSolution { iteration });
         }
     }
 
-    let g = util::h_g(modulus, pubkey, seed);
-    (g == cur_state) || (g == (-cur_state).div_rem_floor(modulus.clone()).1)
+    let g = ctx.h_g(seed);
+    if (g != cur_state) && (g != (-cur_state).div_rem_floor(modulus.clone()).1) {
+        return Err(VerifyError::InvalidProof);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::error::Limit;
+    use super::super::testing::{self, Fixture};
+    use super::*;
+
+    #[test]
+    fn rejects_later_solution() {
+        let modulus = testing::prime_modulus();
+        let pubkey = testing::pubkey();
+        // about one state in 4 meets it, so a few solutions follow each other quickly
+        let target = Integer::from(1) << 254u32;
+        let seed = Integer::from(1);
+        let g = util::h_g(&modulus, &pubkey, &seed);
+        let limits = VerifyLimits::default();
+
+        let (first, first_iters) = mine(1, 1, &pubkey, &modulus, &g, &target);
+        assert_eq!(
+            verify(
+                &modulus,
+                &seed,
+                first_iters,
+                1,
+                &first,
+                &pubkey,
+                &target,
+                &limits
+            ),
+            Ok(())
+        );
+
+        // keep mining past the first solution
+        let (second, more_iters) = mine(1, 1, &pubkey, &modulus, &first, &target);
+        let iters = first_iters + more_iters;
+        assert_eq!(
+            verify(&modulus, &seed, iters, 1, &second, &pubkey, &target, &limits),
+            Err(VerifyError::EarlierSolution {
+                iteration: first_iters
+            })
+        );
+    }
+
+    #[test]
+    fn accepts_honest_solution() {
+        let f = Fixture::sloth();
+        assert_eq!(f.verify(&f.y, &f.proof), Ok(()));
+    }
+
+    #[test]
+    fn rejects_verifier_squarings_over_limit() {
+        let f = Fixture::sloth();
+        let verify_with_max = |max| {
+            let limits = VerifyLimits {
+                max_verifier_squarings: Some(max),
+                ..VerifyLimits::default()
+            };
+            f.verify_with_limits(&f.y, &f.proof, &limits)
+        };
+        assert_eq!(
+            verify_with_max(f.iters - 1),
+            Err(VerifyError::LimitExceeded(Limit::VerifierSquarings))
+        );
+        assert_eq!(verify_with_max(f.iters), Ok(()));
+    }
+
+    #[test]
+    fn rejects_state_missing_target() {
+        let f = Fixture {
+            target: Integer::new(),
+            ..Fixture::sloth()
+        };
+        assert_eq!(f.verify(&f.y, &f.proof), Err(VerifyError::TargetNotMet));
+    }
+
+    #[test]
+    fn rejects_invalid_proof() {
+        // the witness squares back to the g of seed 1, not of seed 2
+        let f = Fixture {
+            seed: Integer::from(2),
+            ..Fixture::sloth()
+        };
+        assert_eq!(f.verify(&f.y, &f.proof), Err(VerifyError::InvalidProof));
+    }
+
+    #[test]
+    fn rejects_unchecked_iteration() {
+        for &(iters, check_interval) in &[(8, 3), (8, 16), (8, 0), (0, 8)] {
+            let f = Fixture {
+                iters,
+                check_interval,
+                ..Fixture::sloth()
+            };
+            assert_eq!(
+                f.verify(&f.y, &f.proof),
+                Err(VerifyError::UncheckedIteration),
+                "{} iterations, checked every {}",
+                iters,
+                check_interval
+            );
+        }
+    }
 }
@@ -0,0 +1,66 @@
+/// Repeated modular squaring engine used by the `solve` loops.
+use super::bigint::{Assign, Integer};
+
+/// In-place modular squaring with a Barrett reduction context for a fixed modulus.
+///
+/// Build it once per modulus and keep it across `solve` steps: the constant costs a long
+/// division. With `gmp` the operands and the scratch integer keep their capacity, so after the
+/// first few steps the hot loop no longer allocates. The `pure-rust` backend still allocates a
+/// new big integer for every product and shift.
+#[derive(Clone)]
+pub struct Squarer {
+    modulus: Integer,
+    // Barrett constant floor(4^k / N), with k the bit length of N
+    mu: Integer,
+    k: u32,
+    scratch: Integer,
+}
+
+impl Squarer {
+    pub fn new(modulus: &Integer) -> Self {
+        let k = modulus.significant_bits();
+        let mu = (Integer::from(1) << (2 * k)) / modulus;
+        Squarer {
+            modulus: modulus.clone(),
+            mu,
+            k,
+            scratch: Integer::new(),
+        }
+    }
+
+    pub fn modulus(&self) -> &Integer {
+        &self.modulus
+    }
+
+    /// x <- x mod N, for 0 <= x < N^2.
+    pub fn reduce(&mut self, x: &mut Integer) {
+        // q = floor(floor(x / 2^(k-1)) * mu / 2^(k+1)) underestimates floor(x / N) by at most 2
+        self.scratch.assign(&*x >> (self.k - 1));
+        self.scratch *= &self.mu;
+        self.scratch >>= self.k + 1;
+        self.scratch *= &self.modulus;
+        *x -= &self.scratch;
+        while *x >= self.modulus {
+            *x -= &self.modulus;
+        }
+    }
+
+    /// x <- x^2 mod N, for 0 <= x < N.
+    pub fn square(&mut self, x: &mut Integer) {
+        x.square_mut();
+        self.reduce(x);
+    }
+
+    /// x <- x^(2^n) mod N, for 0 <= x < N.
+    pub fn square_n(&mut self, x: &mut Integer, n: u64) {
+        for _ in 0..n {
+            self.square(x);
+        }
+    }
+
+    /// x <- x * y mod N, for 0 <= x, y < N.
+    pub fn mul(&mut self, x: &mut Integer, y: &Integer) {
+        *x *= y;
+        self.reduce(x);
+    }
+}
@@ -0,0 +1,188 @@
+/// Mining driven step by step by the caller instead of the blocking `mine` loops.
+use super::bigint::Integer;
+use super::observer::{Control, Observer, Progress, Stopwatch};
+use super::scheme::Scheme;
+use super::squaring::Squarer;
+use ecvrf;
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+use std::iter::FusedIterator;
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+#[cfg(feature = "stream")]
+use std::task::{Context, Poll};
+
+/// Runs one `solve` step per item and yields `(state, iters, diff_valid)` after it, ending after
+/// the first state that meets the target.
+///
+/// With the `stream` feature it is also a `futures_core::Stream`. Every poll runs a whole step
+/// on the polling task, so `step` bounds how long the event loop is blocked.
+pub struct MiningStream<'a, S> {
+    scheme: S,
+    // shared by all steps instead of being rebuilt by every `solve`
+    squarer: Squarer,
+    step: u64,
+    check_interval: u64,
+    pubkey: &'a ecvrf::VrfPk,
+    modulus: &'a Integer,
+    target: &'a Integer,
+    state: Integer,
+    iters: u64,
+    done: bool,
+}
+
+impl<'a, S: Scheme> MiningStream<'a, S> {
+    pub fn new(
+        scheme: S,
+        step: u64,
+        check_interval: u64,
+        pubkey: &'a ecvrf::VrfPk,
+        modulus: &'a Integer,
+        ini_state: &Integer,
+        target: &'a Integer,
+    ) -> Self {
+        MiningStream {
+            scheme,
+            squarer: Squarer::new(modulus),
+            step,
+            check_interval,
+            pubkey,
+            modulus,
+            target,
+            state: ini_state.clone(),
+            iters: 0,
+            done: false,
+        }
+    }
+
+    /// The state after the last step, the initial state before the first one.
+    pub fn state(&self) -> &Integer {
+        &self.state
+    }
+
+    /// Iterations done so far.
+    pub fn iters(&self) -> u64 {
+        self.iters
+    }
+
+    /// Runs the remaining steps like `mine`, reporting to `observer` after each. Returns the
+    /// state meeting the target and its iterations, or `None` if the observer stopped mining.
+    pub fn run<O: Observer + ?Sized>(mut self, observer: &mut O) -> Option<(Integer, u64)> {
+        let stopwatch = Stopwatch::start();
+        while let Some((state, iters, diff_valid)) = self.next() {
+            let progress = Progress {
+                iters,
+                state: &state,
+                modulus: self.modulus,
+                pubkey: self.pubkey,
+                stopwatch: &stopwatch,
+            };
+            let control = observer.on_step(&progress);
+            if diff_valid {
+                return Some((state, iters));
+            }
+            if control == Control::Stop {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+impl<'a, S: Scheme> Iterator for MiningStream<'a, S> {
+    type Item = (Integer, u64, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let (new_state, squarings, diff_valid) = self.scheme.solve_with_squarer(
+            &mut self.squarer,
+            &self.state,
+            self.step,
+            self.check_interval,
+            self.pubkey,
+            self.target,
+        );
+        self.iters += squarings;
+        self.state = new_state;
+        self.done = diff_valid;
+        Some((self.state.clone(), self.iters, diff_valid))
+    }
+}
+
+impl<'a, S: Scheme> FusedIterator for MiningStream<'a, S> {}
+
+#[cfg(feature = "stream")]
+impl<'a, S: Scheme + Unpin> Stream for MiningStream<'a, S> {
+    type Item = (Integer, u64, bool);
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::scheme::Wes19;
+    use super::super::testing;
+    use super::super::{util, wes19};
+    use super::*;
+
+    // about one state in 4 meets it
+    fn target() -> Integer {
+        Integer::from(1) << 254u32
+    }
+
+    #[test]
+    fn yields_every_step_then_ends() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let target = target();
+        let g = util::h_g(&modulus, &pubkey, &Integer::from(1));
+        let mut stream = MiningStream::new(Wes19, 2, 1, &pubkey, &modulus, &g, &target);
+        assert_eq!((stream.state(), stream.iters()), (&g, 0));
+
+        let items: Vec<_> = stream.by_ref().collect();
+        let (mut state, mut iters) = (g.clone(), 0);
+        for (i, item) in items.iter().enumerate() {
+            let (next, squarings, diff_valid) =
+                wes19::solve(&modulus, &state, 2, 1, &pubkey, &target);
+            state = next;
+            iters += squarings;
+            assert_eq!(item, &(state.clone(), iters, diff_valid));
+            assert_eq!(diff_valid, i == items.len() - 1);
+        }
+        assert_eq!((stream.state(), stream.iters()), (&state, iters));
+        let (y, _, mined_iters) = wes19::mine(2, 1, &pubkey, &modulus, &g, &target);
+        assert_eq!((y, mined_iters), (state, iters));
+
+        // fused after the solution
+        assert_eq!(stream.next(), None);
+        assert_eq!(stream.next(), None);
+        assert_eq!(stream.iters(), iters);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn polls_like_the_iterator() {
+        use std::task::Waker;
+
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        let target = target();
+        let g = util::h_g(&modulus, &pubkey, &Integer::from(1));
+        let expected: Vec<_> =
+            MiningStream::new(Wes19, 2, 1, &pubkey, &modulus, &g, &target).collect();
+
+        let mut stream = MiningStream::new(Wes19, 2, 1, &pubkey, &modulus, &g, &target);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut polled = Vec::new();
+        // every poll is ready, a whole step runs on it
+        while let Poll::Ready(Some(item)) = Pin::new(&mut stream).poll_next(&mut cx) {
+            polled.push(item);
+        }
+        assert_eq!(polled, expected);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+    }
+}
@@ -0,0 +1,201 @@
+/// Fixtures shared by the unit tests.
+use super::bigint::Integer;
+use super::context::VerifierContext;
+use super::error::{GroupError, VerifyError};
+use super::limits::VerifyLimits;
+use super::scheme::{Pie19, Scheme, Sloth, Wes19};
+use super::{pie19, sloth, util, wes19};
+
+/// A 512-bit Blum integer, small enough for quick tests, with its factors below.
+pub const MODULUS: &str = "10979532583017385500729242564896469687608603870949360376781504113165402157546943787877575878459667234358404723634997535958478322876870670678016568416808929";
+pub const FACTOR_P: &str =
+    "97948653638130265184122256229065281996643150799084033656217176215413057593507";
+pub const FACTOR_Q: &str =
+    "112094777980114898594095154748105632001512018036361218934967475049865437266347";
+
+/// Prime modulus for sloth (M13 prime), as in `examples/sloth.rs`.
+pub const PRIME_MODULUS: &str = "6864797660130609714981900799081393217269435300143305409394463459185543183397656052122559640661454554977296311391480858037121987999716643812574028291115057151";
+
+pub fn modulus() -> Integer {
+    Integer::from_str_radix(MODULUS, 10).unwrap()
+}
+
+pub fn factors() -> (Integer, Integer) {
+    (
+        Integer::from_str_radix(FACTOR_P, 10).unwrap(),
+        Integer::from_str_radix(FACTOR_Q, 10).unwrap(),
+    )
+}
+
+pub fn prime_modulus() -> Integer {
+    Integer::from_str_radix(PRIME_MODULUS, 10).unwrap()
+}
+
+pub fn pubkey() -> ecvrf::VrfPk {
+    ecvrf::keygen().1
+}
+
+/// A target every state meets.
+pub fn any_target() -> Integer {
+    Integer::from(1) << 256u32
+}
+
+/// The smallest canonical element with Jacobi symbol -1.
+pub fn non_residue(modulus: &Integer) -> Integer {
+    let mut x = Integer::from(2);
+    while x.jacobi(modulus) != -1 {
+        x += 1;
+    }
+    x
+}
+
+/// Some canonical element of QR+_N.
+pub fn qr_plus_element(modulus: &Integer) -> Integer {
+    util::to_qr_plus(modulus, &util::h_g(modulus, &pubkey(), &Integer::from(2)))
+}
+
+/// Iterations of the `Fixture` solutions, every one of them checked.
+pub const FIXTURE_ITERATIONS: u64 = 8;
+
+/// An honest solution from seed 1 under `any_target`, over the test modulus (the prime one for
+/// sloth), found after `FIXTURE_ITERATIONS` with the difficulty checked only there, so
+/// `check_interval` is `FIXTURE_ITERATIONS` too.
+pub struct Fixture<S: Scheme> {
+    pub scheme: S,
+    pub modulus: Integer,
+    pub pubkey: ecvrf::VrfPk,
+    pub target: Integer,
+    pub seed: Integer,
+    pub g: Integer,
+    pub y: Integer,
+    pub proof: S::Proof,
+    pub iters: u64,
+    pub check_interval: u64,
+}
+
+impl<S: Scheme> Fixture<S> {
+    fn mine<F>(scheme: S, modulus: Integer, mine_with: F) -> Self
+    where
+        F: FnOnce(&ecvrf::VrfPk, &Integer, &Integer, &Integer) -> (Integer, S::Proof, u64),
+    {
+        let pubkey = pubkey();
+        let target = any_target();
+        let seed = Integer::from(1);
+        let g = util::h_g(&modulus, &pubkey, &seed);
+        let (y, proof, iters) = mine_with(&pubkey, &modulus, &g, &target);
+        Fixture {
+            scheme,
+            modulus,
+            pubkey,
+            target,
+            seed,
+            g,
+            y,
+            proof,
+            iters,
+            check_interval: FIXTURE_ITERATIONS,
+        }
+    }
+
+    /// `Scheme::verify` of the fixture's statement with the final state `y` and `proof`.
+    pub fn verify(&self, y: &Integer, proof: &S::Proof) -> Result<(), VerifyError> {
+        self.verify_with_limits(y, proof, &VerifyLimits::default())
+    }
+
+    pub fn verify_with_limits(
+        &self,
+        y: &Integer,
+        proof: &S::Proof,
+        limits: &VerifyLimits,
+    ) -> Result<(), VerifyError> {
+        let ctx = VerifierContext::new(&self.modulus, &self.pubkey, &self.target);
+        self.scheme.verify(
+            &ctx,
+            &self.seed,
+            self.iters,
+            self.check_interval,
+            y,
+            proof,
+            limits,
+        )
+    }
+
+    /// Checks that every kind of element outside QR+_N is rejected with its `GroupError`, as
+    /// the final state or as the proof element that `with_element` puts into the honest proof.
+    pub fn assert_rejects_outside_qr_plus<F>(&self, with_element: F)
+    where
+        S::Proof: Clone,
+        F: Fn(Integer) -> S::Proof,
+    {
+        let (modulus, y) = (&self.modulus, &self.y);
+        let rejects = |y: &Integer, proof: S::Proof, e: GroupError| {
+            assert_eq!(
+                self.verify(y, &proof),
+                Err(VerifyError::Group(e)),
+                "{:?}",
+                e
+            );
+        };
+
+        rejects(&Integer::new(), self.proof.clone(), GroupError::OutOfRange);
+        rejects(y, with_element(modulus.clone()), GroupError::OutOfRange);
+        rejects(y, with_element(factors().0), GroupError::NotCoprime);
+        let minus_one = Integer::from(modulus - 1);
+        rejects(&minus_one, self.proof.clone(), GroupError::LowOrder);
+        let negated = Integer::from(modulus - y);
+        rejects(&negated, self.proof.clone(), GroupError::NotCanonical);
+        rejects(
+            y,
+            with_element(non_residue(modulus)),
+            GroupError::NonResidue,
+        );
+    }
+}
+
+impl Fixture<Wes19> {
+    pub fn wes19() -> Self {
+        Fixture::mine(Wes19, modulus(), |pubkey, modulus, g, target| {
+            wes19::mine(
+                FIXTURE_ITERATIONS,
+                FIXTURE_ITERATIONS,
+                pubkey,
+                modulus,
+                g,
+                target,
+            )
+        })
+    }
+}
+
+impl Fixture<Pie19> {
+    /// With depth 1, which leaves 2 squarings of the 8 to the verifier.
+    pub fn pie19() -> Self {
+        Fixture::mine(Pie19, modulus(), |pubkey, modulus, g, target| {
+            pie19::mine(
+                FIXTURE_ITERATIONS,
+                FIXTURE_ITERATIONS,
+                1,
+                pubkey,
+                modulus,
+                g,
+                target,
+            )
+        })
+    }
+}
+
+impl Fixture<Sloth> {
+    pub fn sloth() -> Self {
+        Fixture::mine(Sloth, prime_modulus(), |pubkey, modulus, g, target| {
+            let (witness, iters) = sloth::mine(
+                FIXTURE_ITERATIONS,
+                FIXTURE_ITERATIONS,
+                pubkey,
+                modulus,
+                g,
+                target,
+            );
+            (witness, (), iters)
+        })
+    }
+}
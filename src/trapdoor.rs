@@ -0,0 +1,355 @@
+/// Fast evaluation for test networks through the factorization of the RSA modulus.
+///
+/// Whoever knows the factors of N knows the exponent of the group, λ(N) = lcm(p - 1, q - 1), so
+/// x^(2^T) = x^(2^T mod λ(N)) takes one exponentiation whatever T is. Everything here produces
+/// the same values as the sequential code and verifies with `wes19::verify` and `pie19::verify`,
+/// but the factors must never be known for a real network.
+use super::bigint::Integer;
+use super::pie19::{self, Proof};
+use super::squaring::Squarer;
+use super::util::{self, SplitMix64};
+use ecvrf;
+
+/// An RSA modulus N = p * q together with its factors.
+#[derive(Clone, Debug)]
+pub struct Trapdoor {
+    modulus: Integer,
+    p: Integer,
+    q: Integer,
+    lambda: Integer,
+}
+
+impl Trapdoor {
+    pub fn new(p: Integer, q: Integer) -> Self {
+        assert!(p != q, "the factors of the modulus must be distinct");
+        let p_1 = Integer::from(&p - 1);
+        let q_1 = Integer::from(&q - 1);
+        let gcd = p_1.clone().gcd(&q_1);
+        Trapdoor {
+            modulus: Integer::from(&p * &q),
+            lambda: p_1 * q_1 / gcd,
+            p,
+            q,
+        }
+    }
+
+    /// A `bits`-bit Blum integer from two primes p = q = 3 mod 4 drawn with `SplitMix64` from
+    /// `seed`. Not suitable for anything but tests: the same seed always gives the same factors.
+    pub fn generate(bits: u32, seed: u64) -> Self {
+        assert!(bits >= 16, "modulus must have at least 16 bits");
+        let mut rng = SplitMix64::new(seed);
+        let mut prime = |bits: u32| {
+            // the two top bits set, so that the product has exactly 2 * bits bits
+            let low = Integer::from(1) << (bits - 2);
+            let top = Integer::from(&low * 3);
+            let mut p = (rng.next_integer(&low) + top).next_prime();
+            // QR+_N needs a Blum integer, an odd prime is 3 mod 4 when its bit 1 is set
+            while !p.get_bit(1) {
+                p = p.next_prime();
+            }
+            p
+        };
+        loop {
+            let p = prime(bits / 2);
+            let q = prime(bits - bits / 2);
+            if p != q {
+                return Trapdoor::new(p, q);
+            }
+        }
+    }
+
+    pub fn modulus(&self) -> &Integer {
+        &self.modulus
+    }
+
+    pub fn factors(&self) -> (&Integer, &Integer) {
+        (&self.p, &self.q)
+    }
+
+    /// x^(2^iterations) mod N with a single exponentiation.
+    pub fn eval(&self, x: &Integer, iterations: u64) -> Integer {
+        let exponent = Integer::from(2)
+            .pow_mod(&Integer::from(iterations), &self.lambda)
+            .unwrap();
+        x.clone().pow_mod(&exponent, &self.modulus).unwrap()
+    }
+
+    /// `wes19::solve` and `pie19::solve` through the trapdoor: the same result, but every
+    /// `check_interval` squarings longer than λ(N) is replaced by an exponentiation.
+    pub fn solve(
+        &self,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool) {
+        let mut squarer = Squarer::new(&self.modulus);
+        self.solve_with_squarer(&mut squarer, state, step, check_interval, pubkey, target)
+    }
+
+    /// `solve` reusing the reduction context of `squarer`, a `Squarer` for the modulus.
+    pub fn solve_with_squarer(
+        &self,
+        squarer: &mut Squarer,
+        state: &Integer,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        target: &Integer,
+    ) -> (Integer, u64, bool) {
+        let exponent = Integer::from(2)
+            .pow_mod(&Integer::from(check_interval), &self.lambda)
+            .unwrap();
+        let fast = check_interval > u64::from(self.lambda.significant_bits());
+        util::solve_by_squaring(
+            squarer,
+            state,
+            step,
+            check_interval,
+            pubkey,
+            target,
+            |squarer, y, n| {
+                if fast {
+                    y.pow_mod_mut(&exponent, &self.modulus).unwrap();
+                } else {
+                    squarer.square_n(y, n);
+                }
+            },
+        )
+    }
+
+    /// Runs `solve` until the target is met. Returns the solution and its number of iterations.
+    fn solve_until_found(
+        &self,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        ini_state: &Integer,
+        target: &Integer,
+    ) -> (Integer, u64) {
+        let mut state = ini_state.clone();
+        let mut iters = 0;
+        let mut squarer = Squarer::new(&self.modulus);
+        loop {
+            let (new_state, squarings, diff_valid) =
+                self.solve_with_squarer(&mut squarer, &state, step, check_interval, pubkey, target);
+            state = new_state;
+            iters += squarings;
+            if diff_valid {
+                return (state, iters);
+            }
+        }
+    }
+
+    /// `wes19::prove` through the trapdoor: pi = g^floor(2^T / l) with the quotient reduced
+    /// mod λ(N).
+    pub fn wes19_prove(&self, g: &Integer, iterations: u64, y: &Integer) -> Integer {
+        let g = util::to_qr_plus(&self.modulus, g);
+        let l = util::hash_to_prime(&self.modulus, &[&g, y]);
+
+        // 2^T = q * l + r, and l divides both 2^T - r and l * λ, so
+        // q mod λ = ((2^T mod l * λ) - r) / l
+        let l_lambda = Integer::from(&l * &self.lambda);
+        let m = Integer::from(2)
+            .pow_mod(&Integer::from(iterations), &l_lambda)
+            .unwrap();
+        let r = m.clone().div_rem_floor(l.clone()).1;
+        let quotient = (m - r) / l;
+
+        let pi = g.pow_mod(&quotient, &self.modulus).unwrap();
+        util::to_qr_plus(&self.modulus, &pi)
+    }
+
+    /// `wes19::mine` through the trapdoor.
+    pub fn wes19_mine(
+        &self,
+        step: u64,
+        check_interval: u64,
+        pubkey: &ecvrf::VrfPk,
+        ini_state: &Integer,
+        target: &Integer,
+    ) -> (Integer, Integer, u64) {
+        let (y, iters) = self.solve_until_found(step, check_interval, pubkey, ini_state, target);
+        let pi = self.wes19_prove(ini_state, iters, &y);
+        (y, pi, iters)
+    }
+
+    /// `pie19::prove` through the trapdoor: every midpoint is a single exponentiation.
+    pub fn pie19_prove(&self, g: &Integer, y: &Integer, iterations: u64, depth: u32) -> Proof {
+        pie19::prove_by_halving(&self.modulus, g, y, iterations, depth, |_, x, half| {
+            self.eval(x, half)
+        })
+    }
+
+    /// `pie19::mine` through the trapdoor.
+    pub fn pie19_mine(
+        &self,
+        step: u64,
+        check_interval: u64,
+        depth: u32,
+        pubkey: &ecvrf::VrfPk,
+        ini_state: &Integer,
+        target: &Integer,
+    ) -> (Integer, Proof, u64) {
+        let (y, iters) = self.solve_until_found(step, check_interval, pubkey, ini_state, target);
+        let proof = self.pie19_prove(ini_state, &y, iters, depth);
+        (y, proof, iters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::limits::VerifyLimits;
+    use super::super::testing;
+    use super::super::wes19;
+    use super::*;
+
+    /// Long enough for `solve` to take the exponentiation path, λ(N) has about 511 bits.
+    const CHECK_INTERVAL: u64 = 1024;
+
+    /// About one state in 4 meets it.
+    fn target() -> Integer {
+        Integer::from(1) << 254u32
+    }
+
+    fn trapdoor() -> Trapdoor {
+        let (p, q) = testing::factors();
+        Trapdoor::new(p, q)
+    }
+
+    fn g(modulus: &Integer, pubkey: &ecvrf::VrfPk) -> Integer {
+        util::to_qr_plus(modulus, &util::h_g(modulus, pubkey, &Integer::from(1)))
+    }
+
+    #[test]
+    fn generate_gives_a_blum_integer() {
+        let trapdoor = Trapdoor::generate(512, 7);
+        let (p, q) = trapdoor.factors();
+        assert_eq!(trapdoor.modulus().significant_bits(), 512);
+        assert_eq!(*trapdoor.modulus(), Integer::from(p * q));
+        assert!(p.get_bit(0) && p.get_bit(1) && q.get_bit(0) && q.get_bit(1));
+        assert_eq!(trapdoor.modulus(), Trapdoor::generate(512, 7).modulus());
+    }
+
+    #[test]
+    fn eval_matches_squaring() {
+        let trapdoor = trapdoor();
+        let modulus = trapdoor.modulus();
+        let x = g(modulus, &testing::pubkey());
+        for &iterations in &[0, 1, 2, 100, 1000, 5000] {
+            let mut expected = x.clone();
+            Squarer::new(modulus).square_n(&mut expected, iterations);
+            assert_eq!(trapdoor.eval(&x, iterations), expected);
+        }
+    }
+
+    #[test]
+    fn solve_matches_sequential() {
+        let trapdoor = trapdoor();
+        let modulus = trapdoor.modulus();
+        let pubkey = testing::pubkey();
+        let g = g(modulus, &pubkey);
+        for &check_interval in &[4, CHECK_INTERVAL] {
+            assert_eq!(
+                trapdoor.solve(&g, 4 * check_interval, check_interval, &pubkey, &target()),
+                wes19::solve(
+                    modulus,
+                    &g,
+                    4 * check_interval,
+                    check_interval,
+                    &pubkey,
+                    &target()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn wes19_matches_sequential_and_verifies() {
+        let trapdoor = trapdoor();
+        let modulus = trapdoor.modulus();
+        let pubkey = testing::pubkey();
+        let g = g(modulus, &pubkey);
+
+        for &iterations in &[1, 2, 7, 100, 1000, 5000] {
+            let mut y = g.clone();
+            Squarer::new(modulus).square_n(&mut y, iterations);
+            let y = util::to_qr_plus(modulus, &y);
+            assert_eq!(
+                trapdoor.wes19_prove(&g, iterations, &y),
+                wes19::prove(modulus, &g, iterations, &y),
+                "T = {}",
+                iterations
+            );
+        }
+
+        let step = 4 * CHECK_INTERVAL;
+        let (y, pi, iters) = trapdoor.wes19_mine(step, CHECK_INTERVAL, &pubkey, &g, &target());
+        assert_eq!(
+            (y.clone(), pi.clone(), iters),
+            wes19::mine(step, CHECK_INTERVAL, &pubkey, modulus, &g, &target())
+        );
+        let limits = VerifyLimits::default();
+        assert_eq!(
+            wes19::verify(
+                modulus,
+                &g,
+                iters,
+                CHECK_INTERVAL,
+                &y,
+                &pi,
+                &pubkey,
+                &target(),
+                &limits
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn pie19_matches_sequential_and_verifies() {
+        let trapdoor = trapdoor();
+        let modulus = trapdoor.modulus();
+        let pubkey = testing::pubkey();
+        let g = g(modulus, &pubkey);
+
+        for &iterations in &[1, 2, 7, 100, 1000] {
+            let mut y = g.clone();
+            Squarer::new(modulus).square_n(&mut y, iterations);
+            let y = util::to_qr_plus(modulus, &y);
+            for &depth in &[0, 1, 3] {
+                assert_eq!(
+                    trapdoor.pie19_prove(&g, &y, iterations, depth),
+                    pie19::prove(modulus, &g, &y, iterations, depth),
+                    "T = {}, depth = {}",
+                    iterations,
+                    depth
+                );
+            }
+        }
+
+        let step = 4 * CHECK_INTERVAL;
+        let (y, proof, iters) =
+            trapdoor.pie19_mine(step, CHECK_INTERVAL, 3, &pubkey, &g, &target());
+        assert_eq!(
+            (y.clone(), proof.clone(), iters),
+            pie19::mine(step, CHECK_INTERVAL, 3, &pubkey, modulus, &g, &target())
+        );
+        let limits = VerifyLimits::default();
+        assert_eq!(
+            pie19::verify(
+                modulus,
+                &g,
+                &y,
+                iters,
+                CHECK_INTERVAL,
+                &proof,
+                &pubkey,
+                &target(),
+                &limits
+            ),
+            Ok(())
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use super::bigint::{Integer, Order};
+use super::error::GroupError;
+use super::squaring::Squarer;
 use ecvrf;
-use rug::{integer::Order, Integer};
 use sha2::{Digest, Sha256};
 use std::cmp::Ordering;
 
@@ -12,27 +14,92 @@ pub fn validate_difficulty(state: &Integer, target: &Integer) -> bool {
     (hashed.cmp(target) == Ordering::Less) || (hashed.cmp(target) == Ordering::Equal)
 }
 
-/// int(H("pubkey"||pubkey||"residue"||x)) mod N
-pub fn h_g(modulus: &Integer, pubkey: &ecvrf::VrfPk, seed: &Integer) -> Integer {
+/// Panics unless `step` is a positive multiple of the positive `check_interval`, so that batches
+/// of `step` squarings end on a state the difficulty is checked at.
+pub fn assert_check_interval(step: u64, check_interval: u64) {
+    assert!(check_interval > 0, "check interval must be positive");
+    assert!(
+        step > 0 && step.is_multiple_of(check_interval),
+        "step must be a positive multiple of the check interval"
+    );
+}
+
+/// Whether the difficulty is checked at `iterations`, i.e. it is a positive multiple of
+/// `check_interval`. A solution at any other iteration could not have been found by `mine`.
+pub fn is_checked_iteration(iterations: u64, check_interval: u64) -> bool {
+    check_interval > 0 && iterations > 0 && iterations.is_multiple_of(check_interval)
+}
+
+/// The repeated squaring `solve` of wes19 and pie19: squares `state` up to `step` times in
+/// batches of `check_interval` done by `advance` with `squarer`, and stops at the first state
+/// that meets the target. Returns the last state in QR+_N, the number of squarings done and
+/// whether the state meets the target.
+pub(crate) fn solve_by_squaring<F>(
+    squarer: &mut Squarer,
+    state: &Integer,
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    target: &Integer,
+    mut advance: F,
+) -> (Integer, u64, bool)
+where
+    F: FnMut(&mut Squarer, &mut Integer, u64),
+{
+    assert_check_interval(step, check_interval);
+    let modulus = squarer.modulus().clone();
+    let mut y = state.clone().div_rem_floor(modulus.clone()).1;
+    let mut squarings = 0;
+    loop {
+        advance(squarer, &mut y, check_interval);
+        squarings += check_interval;
+        let y_plus = to_qr_plus(&modulus, &y);
+        let hstate = h_state(&modulus, pubkey, &y_plus);
+        let diff_valid = validate_difficulty(&hstate, target);
+        if diff_valid || squarings == step {
+            return (y_plus, squarings, diff_valid);
+        }
+    }
+}
+
+/// H("pubkey"||pubkey), the prefix shared by `h_g` and `h_state`
+pub fn pubkey_prefix(pubkey: &ecvrf::VrfPk) -> Sha256 {
     let mut hasher = Sha256::new();
     hasher.update("pubkey".as_bytes());
     hasher.update(pubkey.to_bytes());
+    hasher
+}
+
+/// int(H("pubkey"||pubkey||"residue"||x))^-2 mod N, a quadratic residue so that it lies in
+/// QR+_N up to sign
+pub fn h_g(modulus: &Integer, pubkey: &ecvrf::VrfPk, seed: &Integer) -> Integer {
+    h_g_with_prefix(modulus, &pubkey_prefix(pubkey), seed)
+}
+
+/// `h_g` from a precomputed `pubkey_prefix`
+pub fn h_g_with_prefix(modulus: &Integer, prefix: &Sha256, seed: &Integer) -> Integer {
+    let mut hasher = prefix.clone();
     hasher.update("residue".as_bytes());
     hasher.update(seed.to_digits::<u8>(Order::Lsf));
     let hashed = Integer::from_digits(&hasher.finalize(), Order::Lsf);
 
     // invert to get enough security bits
-    match hashed.invert(modulus) {
+    let inverse = match hashed.invert(modulus) {
         Ok(inverse) => inverse,
         Err(unchanged) => unchanged,
-    }
+    };
+    // square, a hash with Jacobi symbol -1 is not in QR+_N
+    inverse.pow_mod(&Integer::from(2), modulus).unwrap()
 }
 
 /// int(H("pubkey"||pubkey||"state"||state)) mod N
 pub fn h_state(modulus: &Integer, pubkey: &ecvrf::VrfPk, state: &Integer) -> Integer {
-    let mut hasher = Sha256::new();
-    hasher.update("pubkey".as_bytes());
-    hasher.update(pubkey.to_bytes());
+    h_state_with_prefix(modulus, &pubkey_prefix(pubkey), state)
+}
+
+/// `h_state` from a precomputed `pubkey_prefix`
+pub fn h_state_with_prefix(modulus: &Integer, prefix: &Sha256, state: &Integer) -> Integer {
+    let mut hasher = prefix.clone();
     hasher.update("state".as_bytes());
     hasher.update(state.to_digits::<u8>(Order::Lsf));
     let hashed = Integer::from_digits(&hasher.finalize(), Order::Lsf);
@@ -76,3 +143,111 @@ pub fn hash_fs(modulus: &Integer, inputs: &[&Integer]) -> Integer {
         Err(unchanged) => unchanged,
     }
 }
+
+/// Canonical representative of `x` in the signed quadratic residue group QR⁺_N,
+/// i.e. |x mod N| = min(x mod N, N - (x mod N)).
+pub fn to_qr_plus(modulus: &Integer, x: &Integer) -> Integer {
+    let x = x.clone().div_rem_floor(modulus.clone()).1;
+    let neg = modulus.clone() - x.clone();
+    if neg < x {
+        neg
+    } else {
+        x
+    }
+}
+
+/// Checks that `x` is a canonical element of QR⁺_N that is safe to feed into a verifier.
+pub fn check_qr_plus(modulus: &Integer, x: &Integer) -> Result<(), GroupError> {
+    if *x < 1 || x >= modulus {
+        return Err(GroupError::OutOfRange);
+    }
+    if x.clone().gcd(modulus) != 1 {
+        return Err(GroupError::NotCoprime);
+    }
+    // elements of order 2 (-1 and the non-trivial square roots of 1) square to 1,
+    // the identity itself is harmless and is what wes19::prove outputs for small iterations
+    if *x != 1 && x.clone().pow_mod(&Integer::from(2), modulus).unwrap() == 1 {
+        return Err(GroupError::LowOrder);
+    }
+    // canonical representatives lie in [1, (N-1)/2]
+    if x.clone() * 2 > *modulus {
+        return Err(GroupError::NotCanonical);
+    }
+    // for a Blum integer N, QR+_N is exactly the canonical elements with Jacobi symbol 1
+    if x.jacobi(modulus) != 1 {
+        return Err(GroupError::NonResidue);
+    }
+    Ok(())
+}
+
+/// SplitMix64, a small deterministic generator for simulations and sampling, not for keys.
+#[derive(Clone, Debug)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform-ish in [0, N), from as many random bits as N has, reduced mod N.
+    pub fn next_integer(&mut self, modulus: &Integer) -> Integer {
+        let bytes = modulus.significant_bits().div_ceil(8) as usize;
+        let digits: Vec<u8> = (0..bytes).map(|_| self.next_u64() as u8).collect();
+        Integer::from_digits(&digits, Order::Lsf)
+            .div_rem_floor(modulus.clone())
+            .1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing;
+    use super::*;
+
+    #[test]
+    fn jacobi_matches_euler_criterion() {
+        // for a prime p, (x / p) = x^((p - 1) / 2) mod p
+        let p = testing::prime_modulus();
+        let exponent = Integer::from(&p - 1) / 2;
+        for x in 1..200u32 {
+            let x = Integer::from(x);
+            let euler = x.clone().pow_mod(&exponent, &p).unwrap();
+            let expected = if euler == 1 { 1 } else { -1 };
+            assert_eq!(x.jacobi(&p), expected);
+        }
+    }
+
+    #[test]
+    fn checked_iterations_are_positive_multiples() {
+        assert!(is_checked_iteration(8, 4));
+        assert!(is_checked_iteration(8, 8));
+        assert!(is_checked_iteration(8, 1));
+        assert!(!is_checked_iteration(8, 3));
+        assert!(!is_checked_iteration(8, 16));
+        assert!(!is_checked_iteration(0, 4));
+        assert!(!is_checked_iteration(8, 0));
+    }
+
+    #[test]
+    fn h_g_lies_in_qr_plus() {
+        let modulus = testing::modulus();
+        let pubkey = testing::pubkey();
+        for seed in 0..32u32 {
+            let g = to_qr_plus(&modulus, &h_g(&modulus, &pubkey, &Integer::from(seed)));
+            assert_eq!(check_qr_plus(&modulus, &g), Ok(()));
+        }
+    }
+}
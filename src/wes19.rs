@@ -1,94 +1,467 @@
+use super::bigint::Integer;
+use super::checkpoint::Checkpoints;
+use super::context::VerifierContext;
+use super::error::VerifyError;
+use super::fixed_base::FixedBaseTable;
+use super::limits::{Deadline, VerifyLimits};
+use super::observer::{NoopObserver, Observer};
+use super::report::MiningReport;
+use super::scheme::Wes19;
+use super::squaring::Squarer;
+use super::stream::MiningStream;
 use super::util;
 use ecvrf;
-use rug::Integer;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 pub fn mine(
     step: u64,
+    check_interval: u64,
     pubkey: &ecvrf::VrfPk,
     modulus: &Integer,
     ini_state: &Integer,
     target: &Integer,
 ) -> (Integer, Integer, u64) {
+    mine_with_observer(
+        step,
+        check_interval,
+        pubkey,
+        modulus,
+        ini_state,
+        target,
+        &mut NoopObserver,
+    )
+    .expect("mining without an observer does not stop")
+}
+
+/// `mine` reporting its progress to `observer` after every `solve` step. Returns `None` if the
+/// observer stopped it before a solution was found.
+pub fn mine_with_observer<O: Observer + ?Sized>(
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    modulus: &Integer,
+    ini_state: &Integer,
+    target: &Integer,
+    observer: &mut O,
+) -> Option<(Integer, Integer, u64)> {
+    let stream = MiningStream::new(
+        Wes19,
+        step,
+        check_interval,
+        pubkey,
+        modulus,
+        ini_state,
+        target,
+    );
+    let (cur_state, iters) = stream.run(observer)?;
+
+    let pi = prove(modulus, ini_state, iters, &cur_state);
+
+    Some((cur_state, pi, iters))
+}
+
+/// `mine` also returning how many squarings and checks it did and how long it spent solving
+/// and proving.
+pub fn mine_with_report(
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    modulus: &Integer,
+    ini_state: &Integer,
+    target: &Integer,
+) -> ((Integer, Integer, u64), MiningReport) {
+    MiningReport::measure(
+        check_interval,
+        |observer| {
+            mine_with_observer(
+                step,
+                check_interval,
+                pubkey,
+                modulus,
+                ini_state,
+                target,
+                observer,
+            )
+        },
+        |&(_, _, iters)| iters,
+    )
+}
+
+/// Squares `state` up to `step` times, checking the difficulty every `check_interval` squarings
+/// and stopping at the first state that meets the target. Returns that last state, the number
+/// of squarings done and whether it meets the target.
+pub fn solve(
+    modulus: &Integer,
+    state: &Integer,
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    target: &Integer,
+) -> (Integer, u64, bool) {
+    let mut squarer = Squarer::new(modulus);
+    solve_with_squarer(&mut squarer, state, step, check_interval, pubkey, target)
+}
+
+/// `solve` with the reduction context of a `Squarer` for the modulus, which repeated calls
+/// such as the steps of a `MiningStream` can share instead of building one per step.
+pub fn solve_with_squarer(
+    squarer: &mut Squarer,
+    state: &Integer,
+    step: u64,
+    check_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    target: &Integer,
+) -> (Integer, u64, bool) {
+    util::solve_by_squaring(
+        squarer,
+        state,
+        step,
+        check_interval,
+        pubkey,
+        target,
+        |squarer, y, n| squarer.square_n(y, n),
+    )
+}
+
+/// `mine` keeping a checkpoint every `checkpoint_interval` squarings, so the proof is computed
+/// by `prove_with_checkpoints` (in parallel with the `parallel` feature).
+pub fn mine_with_checkpoints(
+    step: u64,
+    check_interval: u64,
+    checkpoint_interval: u64,
+    pubkey: &ecvrf::VrfPk,
+    modulus: &Integer,
+    ini_state: &Integer,
+    target: &Integer,
+) -> (Integer, Integer, u64) {
+    let mut checkpoints = Checkpoints::new(modulus, ini_state, checkpoint_interval);
     let mut cur_state = ini_state.clone();
     let mut iters: u64 = 0;
+    let mut squarer = Squarer::new(modulus);
 
     loop {
-        iters += step;
-        let (new_state, diff_valid) = solve(modulus, &cur_state, step, pubkey, target);
+        let (new_state, squarings, diff_valid) = solve_with_checkpoints(
+            &mut squarer,
+            &cur_state,
+            step,
+            check_interval,
+            pubkey,
+            target,
+            &mut checkpoints,
+        );
+        iters += squarings;
         cur_state = new_state;
         if diff_valid {
             break;
         }
     }
 
-    let pi = prove(modulus, ini_state, iters, &cur_state);
+    let pi = prove_with_checkpoints(modulus, ini_state, iters, &cur_state, &checkpoints);
 
-    (cur_state.clone(), pi, iters)
+    (cur_state, pi, iters)
 }
 
-pub fn solve(
-    modulus: &Integer,
+/// `solve_with_squarer` recording the intermediate states in `checkpoints`, whose chain must
+/// end at `state`.
+pub fn solve_with_checkpoints(
+    squarer: &mut Squarer,
     state: &Integer,
     step: u64,
+    check_interval: u64,
     pubkey: &ecvrf::VrfPk,
     target: &Integer,
-) -> (Integer, bool) {
-    let mut y = state.clone();
+    checkpoints: &mut Checkpoints,
+) -> (Integer, u64, bool) {
+    util::solve_by_squaring(
+        squarer,
+        state,
+        step,
+        check_interval,
+        pubkey,
+        target,
+        |squarer, y, n| checkpoints.advance(squarer, y, n),
+    )
+}
 
-    for _ in 0..step {
-        y = y.clone() * y.clone();
-        y = y.div_rem_floor(modulus.clone()).1;
-    }
+/// Number of precomputed powers of `g` used by `prove`.
+pub const DEFAULT_TABLE_ENTRIES: usize = 256;
 
-    let hstate = util::h_state(modulus, pubkey, &y);
-    (y, util::validate_difficulty(&hstate, target))
+pub fn prove(modulus: &Integer, g: &Integer, iterations: u64, y: &Integer) -> Integer {
+    prove_with_table(modulus, g, iterations, y, DEFAULT_TABLE_ENTRIES)
 }
 
-pub fn prove(modulus: &Integer, g: &Integer, iterations: u64, y: &Integer) -> Integer {
+/// `prove` keeping at most `max_table_entries` precomputed powers of `g` in memory.
+pub fn prove_with_table(
+    modulus: &Integer,
+    g: &Integer,
+    iterations: u64,
+    y: &Integer,
+    max_table_entries: usize,
+) -> Integer {
+    let g = &util::to_qr_plus(modulus, g);
     let l = util::hash_to_prime(modulus, &[g, y]);
 
-    // algo_4 from the paper, long division
-    // TODO: consider algo_5 instead
-    let mut b: Integer;
+    // algo_4 from the paper, long division, emitting k bits of floor(2^T / l) per round so that
+    // g^b comes from a table of g^0..g^(2^k - 1) instead of an exponentiation
+    let mut squarer = Squarer::new(modulus);
+    let k = (usize::BITS - 1 - max_table_entries.max(2).leading_zeros()).min(16);
+    let table = FixedBaseTable::new(&mut squarer, g, k, max_table_entries.max(2));
+    let k = table.window();
+
     let mut r = Integer::from(1);
-    let mut r2: Integer;
-    let two = Integer::from(2);
     let mut pi = Integer::from(1);
+    let mut remaining = iterations;
+    while remaining > 0 {
+        let chunk = remaining.min(u64::from(k)) as u32;
+        let (b, rem) = (r << chunk).div_rem_floor(l.clone());
+        r = rem;
+        squarer.square_n(&mut pi, u64::from(chunk));
+        if b != 0 {
+            let g_b = table.pow(&mut squarer, &b);
+            squarer.mul(&mut pi, &g_b);
+        }
+        remaining -= u64::from(chunk);
+    }
+
+    util::to_qr_plus(modulus, &pi)
+}
+
+/// `prove` from the states `g^(2^(j * S))` stored during solving.
+///
+/// Writing q = floor(2^T / l) in base 2^S as sum(q_j * 2^(j * S)), the proof is
+/// g^q = prod((g^(2^(j * S)))^(q_j)), where every factor only needs its own checkpoint and
+/// q_j = floor((2^(T - j * S) mod (l * 2^S)) / l). The factors are independent, and are
+/// computed on all cores with the `parallel` feature.
+pub fn prove_with_checkpoints(
+    modulus: &Integer,
+    g: &Integer,
+    iterations: u64,
+    y: &Integer,
+    checkpoints: &Checkpoints,
+) -> Integer {
+    assert!(
+        checkpoints.iterations() >= iterations,
+        "checkpoints do not cover the iterations"
+    );
+    let g = &util::to_qr_plus(modulus, g);
+    let l = util::hash_to_prime(modulus, &[g, y]);
+
+    let interval = checkpoints.interval();
+    let l_shifted = l.clone() << interval as u32;
+    let segment = |j: u64| -> Integer {
+        let shift = iterations - j * interval;
+        let two_exp = Integer::from(2)
+            .pow_mod(&Integer::from(shift), &l_shifted)
+            .unwrap();
+        let q_j = two_exp / &l;
+        checkpoints.states()[j as usize]
+            .clone()
+            .pow_mod(&q_j, modulus)
+            .unwrap()
+    };
+    let segments = if iterations == 0 {
+        0
+    } else {
+        (iterations - 1) / interval + 1
+    };
+
+    #[cfg(feature = "parallel")]
+    let partials: Vec<Integer> = (0..segments).into_par_iter().map(segment).collect();
+    #[cfg(not(feature = "parallel"))]
+    let partials: Vec<Integer> = (0..segments).map(segment).collect();
 
-    for _ in 0..iterations {
-        r2 = r.clone() * two.clone();
-        b = r2.clone().div_rem_floor(l.clone()).0;
-        r = r2.clone().div_rem_floor(l.clone()).1;
-        let pi_2 = pi.clone().pow_mod(&two, modulus).unwrap();
-        let g_b = g.clone().pow_mod(&b, modulus).unwrap();
-        pi = pi_2 * g_b;
+    let mut squarer = Squarer::new(modulus);
+    let mut pi = Integer::from(1);
+    for partial in &partials {
+        squarer.mul(&mut pi, partial);
     }
 
-    Integer::from(pi.div_rem_floor(modulus.clone()).1)
+    util::to_qr_plus(modulus, &pi)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn verify(
     modulus: &Integer,
     g: &Integer,
     iterations: u64,
+    check_interval: u64,
     y: &Integer,
     pi: &Integer,
     pubkey: &ecvrf::VrfPk,
     target: &Integer,
-) -> bool {
-    let hstate = util::h_state(modulus, pubkey, y);
-    if !util::validate_difficulty(&hstate, target) {
-        return false;
+    limits: &VerifyLimits,
+) -> Result<(), VerifyError> {
+    let ctx = VerifierContext::new(modulus, pubkey, target);
+    verify_with_context(&ctx, g, iterations, check_interval, y, pi, limits)
+}
+
+/// `verify` reusing the precomputed data in `ctx`
+pub fn verify_with_context(
+    ctx: &VerifierContext,
+    g: &Integer,
+    iterations: u64,
+    check_interval: u64,
+    y: &Integer,
+    pi: &Integer,
+    limits: &VerifyLimits,
+) -> Result<(), VerifyError> {
+    let deadline = Deadline::start(limits);
+    limits.check_iterations(iterations)?;
+    for x in &[g, y, pi] {
+        limits.check_element(x)?;
+    }
+    if !util::is_checked_iteration(iterations, check_interval) {
+        return Err(VerifyError::UncheckedIteration);
+    }
+
+    let modulus = ctx.modulus();
+
+    // work over QR+_N and reject anything outside of it before exponentiating
+    let g = &util::to_qr_plus(modulus, g);
+    util::check_qr_plus(modulus, g)?;
+    util::check_qr_plus(modulus, y)?;
+    util::check_qr_plus(modulus, pi)?;
+
+    if !ctx.validate_difficulty(y) {
+        return Err(VerifyError::TargetNotMet);
     }
 
     let l = util::hash_to_prime(modulus, &[g, y]);
+    deadline.check()?;
 
     let r = Integer::from(2)
         .pow_mod(&Integer::from(iterations), &l)
         .unwrap();
-    let pi_l = pi.clone().pow_mod(&l, modulus).unwrap();
+    let mut pi_l_g_r = pi.clone().pow_mod(&l, modulus).unwrap();
     let g_r = g.clone().pow_mod(&r, modulus).unwrap();
-    let pi_l_g_r = pi_l * g_r;
+    ctx.squarer().mul(&mut pi_l_g_r, &g_r);
 
-    Integer::from(pi_l_g_r.div_rem_floor(modulus.clone()).1) == y.clone()
+    if util::to_qr_plus(modulus, &pi_l_g_r) != *y {
+        return Err(VerifyError::InvalidProof);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, Fixture};
+    use super::*;
+
+    /// `g` squared `iterations` times while recording `checkpoints`, in QR+_N.
+    fn square_with_checkpoints(
+        modulus: &Integer,
+        g: &Integer,
+        iterations: u64,
+        checkpoints: &mut Checkpoints,
+    ) -> Integer {
+        let mut squarer = Squarer::new(modulus);
+        let mut y = g.clone();
+        checkpoints.advance(&mut squarer, &mut y, iterations);
+        util::to_qr_plus(modulus, &y)
+    }
+
+    #[test]
+    fn prove_with_checkpoints_matches_prove() {
+        let modulus = testing::modulus();
+        let g = util::h_g(&modulus, &testing::pubkey(), &Integer::from(1));
+        for &iterations in &[1, 2, 7, 64, 100, 257, 1000] {
+            for &interval in &[1, 3, 16, 1000] {
+                let mut checkpoints = Checkpoints::new(&modulus, &g, interval);
+                let y = square_with_checkpoints(&modulus, &g, iterations, &mut checkpoints);
+                assert_eq!(
+                    prove_with_checkpoints(&modulus, &g, iterations, &y, &checkpoints),
+                    prove(&modulus, &g, iterations, &y),
+                    "T = {}, interval = {}",
+                    iterations,
+                    interval
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn prove_with_budget_checkpoints_matches_prove() {
+        let modulus = testing::modulus();
+        let g = util::h_g(&modulus, &testing::pubkey(), &Integer::from(1));
+        for &iterations in &[1, 2, 7, 64, 100, 257, 1000] {
+            for &max_states in &[2, 5, 32] {
+                let mut checkpoints = Checkpoints::with_budget(&modulus, &g, max_states);
+                let y = square_with_checkpoints(&modulus, &g, iterations, &mut checkpoints);
+                assert_eq!(
+                    prove_with_checkpoints(&modulus, &g, iterations, &y, &checkpoints),
+                    prove(&modulus, &g, iterations, &y),
+                    "T = {}, budget = {}",
+                    iterations,
+                    max_states
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mine_with_checkpoints_matches_mine() {
+        let f = Fixture::wes19();
+        assert_eq!(
+            mine_with_checkpoints(8, 8, 3, &f.pubkey, &f.modulus, &f.g, &f.target),
+            (f.y, f.proof, f.iters)
+        );
+    }
+
+    #[test]
+    fn accepts_honest_solution() {
+        let f = Fixture::wes19();
+        assert_eq!(f.verify(&f.y, &f.proof), Ok(()));
+    }
+
+    #[test]
+    fn rejects_elements_outside_qr_plus() {
+        let f = Fixture::wes19();
+        f.assert_rejects_outside_qr_plus(|pi| pi);
+    }
+
+    #[test]
+    fn rejects_state_missing_target() {
+        let f = Fixture {
+            target: Integer::new(),
+            ..Fixture::wes19()
+        };
+        assert_eq!(f.verify(&f.y, &f.proof), Err(VerifyError::TargetNotMet));
+    }
+
+    #[test]
+    fn rejects_invalid_proof() {
+        let f = Fixture::wes19();
+        let pi = testing::qr_plus_element(&f.modulus);
+        assert_ne!(pi, f.proof);
+        assert_eq!(f.verify(&f.y, &pi), Err(VerifyError::InvalidProof));
+
+        // an honest proof for another seed's g
+        let other_seed = Fixture {
+            seed: Integer::from(2),
+            ..Fixture::wes19()
+        };
+        assert_eq!(
+            other_seed.verify(&other_seed.y, &other_seed.proof),
+            Err(VerifyError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn rejects_unchecked_iteration() {
+        for &(iters, check_interval) in &[(8, 3), (8, 16), (8, 0), (0, 8)] {
+            let f = Fixture {
+                iters,
+                check_interval,
+                ..Fixture::wes19()
+            };
+            assert_eq!(
+                f.verify(&f.y, &f.proof),
+                Err(VerifyError::UncheckedIteration),
+                "{} iterations, checked every {}",
+                iters,
+                check_interval
+            );
+        }
+    }
 }
@@ -0,0 +1,184 @@
+//! Runs the schemes over both big-integer backends and compares the results.
+//!
+//! `seq_pow` is built over `rug::Integer` here, `seq_pow_pure` is the same sources built again
+//! with the `pure-rust` feature, see `tests/pure-backend`.
+#![cfg(feature = "gmp")]
+
+#[allow(dead_code, clippy::useless_conversion)]
+#[path = "../src/testing.rs"]
+mod testing;
+
+// `testing` finds the crate modules at the root, as in the library
+use seq_pow::{bigint, context, error, limits, pie19, scheme, sloth, util, wes19};
+
+use bigint::Integer as Gmp;
+use limits::VerifyLimits;
+use seq_pow_pure::bigint::Integer as Pure;
+use seq_pow_pure::limits::VerifyLimits as PureLimits;
+
+fn pure(x: &Gmp) -> Pure {
+    Pure::from_str_radix(&x.to_string(), 10).unwrap()
+}
+
+/// About one state in 16 meets it.
+fn target() -> Gmp {
+    Gmp::from(1) << 252u32
+}
+
+#[test]
+fn integer_ops_agree() {
+    let modulus = testing::modulus();
+    let (p, _) = testing::factors();
+    for x in &[
+        Gmp::from(2),
+        Gmp::from(65537),
+        p - 1,
+        Gmp::from(&modulus - 2),
+    ] {
+        let e = Gmp::from(x * 3) + 1;
+        assert_eq!(pure(&x.clone().next_prime()), pure(x).next_prime());
+        assert_eq!(
+            pure(&x.clone().invert(&modulus).unwrap()),
+            pure(x).invert(&pure(&modulus)).unwrap()
+        );
+        assert_eq!(
+            pure(&x.clone().pow_mod(&e, &modulus).unwrap()),
+            pure(x).pow_mod(&pure(&e), &pure(&modulus)).unwrap()
+        );
+        assert_eq!(x.jacobi(&modulus), pure(x).jacobi(&pure(&modulus)));
+    }
+}
+
+#[test]
+fn wes19_agrees() {
+    let modulus = testing::modulus();
+    let pubkey = testing::pubkey();
+    let g = util::h_g(&modulus, &pubkey, &Gmp::from(7));
+    let pure_g = seq_pow_pure::util::h_g(&pure(&modulus), &pubkey, &Pure::from(7));
+    assert_eq!(pure(&g), pure_g);
+
+    let (y, pi, iters) = wes19::mine(16, 16, &pubkey, &modulus, &g, &target());
+    let (pure_y, pure_pi, pure_iters) =
+        seq_pow_pure::wes19::mine(16, 16, &pubkey, &pure(&modulus), &pure_g, &pure(&target()));
+    assert_eq!((pure(&y), pure(&pi), iters), (pure_y, pure_pi, pure_iters));
+
+    assert_eq!(
+        wes19::verify(
+            &modulus,
+            &g,
+            iters,
+            16,
+            &y,
+            &pi,
+            &pubkey,
+            &target(),
+            &VerifyLimits::default()
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        seq_pow_pure::wes19::verify(
+            &pure(&modulus),
+            &pure(&g),
+            iters,
+            16,
+            &pure(&y),
+            &pure(&pi),
+            &pubkey,
+            &pure(&target()),
+            &PureLimits::default()
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn pie19_agrees() {
+    let modulus = testing::modulus();
+    let pubkey = testing::pubkey();
+    let g = util::h_g(&modulus, &pubkey, &Gmp::from(7));
+
+    let (y, proof, iters) = pie19::mine(16, 16, 2, &pubkey, &modulus, &g, &target());
+    let (pure_y, pure_proof, pure_iters) = seq_pow_pure::pie19::mine(
+        16,
+        16,
+        2,
+        &pubkey,
+        &pure(&modulus),
+        &pure(&g),
+        &pure(&target()),
+    );
+    assert_eq!((pure(&y), iters), (pure_y.clone(), pure_iters));
+    assert_eq!(proof.depth, pure_proof.depth);
+    let pi_list: Vec<Pure> = proof.pi_list.iter().map(pure).collect();
+    assert_eq!(pi_list, pure_proof.pi_list);
+
+    assert_eq!(
+        pie19::verify(
+            &modulus,
+            &g,
+            &y,
+            iters,
+            16,
+            &proof,
+            &pubkey,
+            &target(),
+            &VerifyLimits::default()
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        seq_pow_pure::pie19::verify(
+            &pure(&modulus),
+            &pure(&g),
+            &pure_y,
+            iters,
+            16,
+            &pure_proof,
+            &pubkey,
+            &pure(&target()),
+            &PureLimits::default()
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn sloth_agrees() {
+    let modulus = testing::prime_modulus();
+    let pubkey = testing::pubkey();
+    let seed = Gmp::from(7);
+    let g = util::h_g(&modulus, &pubkey, &seed);
+
+    let (witness, iters) = sloth::mine(4, 4, &pubkey, &modulus, &g, &target());
+    let (pure_witness, pure_iters) =
+        seq_pow_pure::sloth::mine(4, 4, &pubkey, &pure(&modulus), &pure(&g), &pure(&target()));
+    assert_eq!((pure(&witness), iters), (pure_witness.clone(), pure_iters));
+
+    assert_eq!(
+        sloth::verify(
+            &modulus,
+            &seed,
+            iters,
+            4,
+            &witness,
+            &pubkey,
+            &target(),
+            &VerifyLimits::default()
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        seq_pow_pure::sloth::verify(
+            &pure(&modulus),
+            &pure(&seed),
+            iters,
+            4,
+            &pure_witness,
+            &pubkey,
+            &pure(&target()),
+            &PureLimits::default()
+        ),
+        Ok(())
+    );
+}
@@ -0,0 +1,91 @@
+//! Mines and verifies small solutions of every scheme. Runs natively like any test, and under
+//! WebAssembly with
+//! `cargo test --target wasm32-wasip1 --no-default-features --features pure-rust --test wasm`,
+//! see the README.
+
+#[allow(dead_code, clippy::useless_conversion)]
+#[path = "../src/testing.rs"]
+mod testing;
+
+// `testing` finds the crate modules at the root, as in the library
+use seq_pow::{bigint, context, error, limits, pie19, scheme, sloth, util, wes19};
+
+use bigint::Integer;
+use limits::VerifyLimits;
+use scheme::Wes19;
+use seq_pow::scheduler::Scheduler;
+
+/// About one state in 4 meets it.
+fn target() -> Integer {
+    Integer::from(1) << 254u32
+}
+
+#[test]
+fn wes19_solution_verifies() {
+    let modulus = testing::modulus();
+    let pubkey = testing::pubkey();
+    let g = util::h_g(&modulus, &pubkey, &Integer::from(1));
+    let (y, pi, iters) = wes19::mine(8, 8, &pubkey, &modulus, &g, &target());
+    let limits = VerifyLimits::default();
+    assert_eq!(
+        wes19::verify(&modulus, &g, iters, 8, &y, &pi, &pubkey, &target(), &limits),
+        Ok(())
+    );
+}
+
+#[test]
+fn pie19_solution_verifies() {
+    let modulus = testing::modulus();
+    let pubkey = testing::pubkey();
+    let g = util::h_g(&modulus, &pubkey, &Integer::from(1));
+    let (y, proof, iters) = pie19::mine(8, 8, 2, &pubkey, &modulus, &g, &target());
+    let limits = VerifyLimits::default();
+    assert_eq!(
+        pie19::verify(
+            &modulus,
+            &g,
+            &y,
+            iters,
+            8,
+            &proof,
+            &pubkey,
+            &target(),
+            &limits
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn sloth_solution_verifies() {
+    let modulus = testing::prime_modulus();
+    let pubkey = testing::pubkey();
+    let seed = Integer::from(1);
+    let g = util::h_g(&modulus, &pubkey, &seed);
+    let (witness, iters) = sloth::mine(2, 2, &pubkey, &modulus, &g, &target());
+    let limits = VerifyLimits::default();
+    assert_eq!(
+        sloth::verify(
+            &modulus,
+            &seed,
+            iters,
+            2,
+            &witness,
+            &pubkey,
+            &target(),
+            &limits
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn scheduler_runs_several_jobs() {
+    let modulus = testing::modulus();
+    let pubkey = testing::pubkey();
+    let target = target();
+    let mut scheduler = Scheduler::new(Wes19, &modulus, 8, 8, 2);
+    scheduler.add_job(&Integer::from(1), &pubkey, &target, 0);
+    scheduler.add_job(&Integer::from(2), &pubkey, &target, 0);
+    assert!(!scheduler.run_until_solution().is_empty());
+}